@@ -3,31 +3,138 @@
  * https://adventofcode.com/2022/day/3
  ********************************************/
 
-use std::{path::Path, fs::File, io::Read, collections::HashSet};
+use std::{path::Path, fs::File, io::Read, collections::{HashSet, HashMap, BTreeSet}, env, fmt};
 
 const _DEBUG: bool = false;
+const DEFAULT_GROUP_SIZE: usize = 3;
+const MAX_EXTRA_GROUP_REPAIR_SWAPS: usize = 2;
+const MAX_GROUP_REPAIR_STEPS: usize = 1_000_000;
+
+#[derive(Debug)]
+struct PriorityScheme {
+    priorities: HashMap<char, i32>,
+}
+
+#[derive(Debug)]
+enum Violation {
+    UnevenLength { line: usize, length: usize },
+    InvalidItemType { line: usize, item_type: char },
+    NoMisplacedItem { line: usize },
+    MultipleMisplacedItems { line: usize, item_types: Vec<char> },
+    IncompleteGroup { first_line: usize, size: usize },
+    NoBadge { first_line: usize },
+    MultipleBadges { first_line: usize, badges: Vec<char> },
+}
+
+#[derive(Debug)]
+enum RepairTarget {
+    Rucksack { line: usize },
+    Group { first_line: usize },
+}
+
+#[derive(Debug)]
+struct GroupRepairSearch<'a> {
+    scheme: &'a PriorityScheme,
+    counts: Vec<HashMap<char, usize>>, // items of each type per rucksack, kept up to date while swapping
+    broken: HashSet<usize>, // lines of rucksacks that were invalid before the repair
+    limit: usize,
+    steps: usize,
+    searched: HashMap<Vec<Vec<char>>, usize>, // swaps that were left when a state was searched
+}
+
+#[derive(Debug)]
+struct Repair {
+    target: RepairTarget,
+    swaps: Result<usize, String>, // why no repair was found otherwise
+}
 
 fn main() {
+    // usage: day3 [group size] [--sliding] [--scheme=letters|digits|ascii|extended|alphabet:<items>|weights:<file>]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let sliding = args.iter().any(|arg| arg == "--sliding");
+    let scheme = match args.iter().find_map(|arg| arg.strip_prefix("--scheme=")) {
+        Some(name) => PriorityScheme::from_name(name).unwrap_or_else(|error| panic!("Invalid priority scheme {} - error: {}", name, error)),
+        None => PriorityScheme::letters(),
+    };
+    let group_size = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(0) | Err(_) => panic!("Invalid group size {}, expected a positive number", arg),
+            Ok(group_size) => group_size,
+        },
+        None => DEFAULT_GROUP_SIZE,
+    };
+
     println!("Reading input file...");
     let input_string = read_input();
 
+    assert_eq!(Some((0, "abca".to_string())), repair_rucksack("abca"));
+    assert_eq!(Some((1, "abbacc".to_string())), repair_rucksack("abcabc"));
+    assert_eq!(Some(1), calc_rucksack_repair_swaps("aacb"));
+    assert_eq!(Some(1), calc_rucksack_repair_swaps("aabbabcc"));
+    assert_eq!(Some(2), calc_rucksack_repair_swaps("aabbccabcdde"));
+    assert_eq!(None, calc_rucksack_repair_swaps("abab"));
+    assert_eq!(None, calc_rucksack_repair_swaps("abcd"));
+    assert_eq!(None, calc_rucksack_repair_swaps("abc"));
+    let letters = PriorityScheme::letters();
+    let group = |rucksacks: &[&str]| rucksacks.iter().map(|rucksack| rucksack.to_string()).collect::<Vec<String>>();
+    assert_eq!(Ok(0), calc_group_repair_swaps(&group(&["abca", "dbbd", "ebfe"]), &letters));
+    assert_eq!(Ok(1), calc_group_repair_swaps(&group(&["afef", "fg", "ed"]), &letters));
+    assert_eq!(Ok(2), calc_group_repair_swaps(&group(&["cdgfcc", "ba", "db"]), &letters));
+    assert_eq!(Ok(3), calc_group_repair_swaps(&group(&["gffffb", "aa", "fc"]), &letters));
+    assert_eq!(Err("cannot be repaired by swapping items".to_string()), calc_group_repair_swaps(&group(&["cb", "dc", "ef"]), &letters));
+    // every letter twice in each rucksack makes 52 badges, the search has to give up within its step limit
+    let crowded: String = ('a'..='z').chain('A'..='Z').collect::<String>().repeat(2);
+    assert!(calc_group_repair_swaps(&group(&[&crowded, &crowded, &crowded]), &letters).is_err());
+
+    let mut violations = validate_rucksacks(input_string.as_str(), &scheme);
+    if !sliding {
+        violations.extend(validate_groups(input_string.as_str(), group_size));
+    }
+    if !violations.is_empty() {
+        println!("Found {} violations in input:", violations.len());
+        for violation in &violations {
+            println!("  {}", violation);
+        }
+        let mut repairs = plan_rucksack_repairs(input_string.as_str());
+        if !sliding {
+            repairs.extend(plan_group_repairs(input_string.as_str(), group_size, &scheme));
+        }
+        println!("Repair plan:");
+        for repair in &repairs {
+            println!("  {}", repair);
+        }
+        let total_swaps: usize = repairs.iter().flat_map(|repair| repair.swaps.as_ref().ok()).sum();
+        println!("Minimum number of item swaps for all rucksacks and groups with a repair: {}", total_swaps);
+        return;
+    }
+
     if _DEBUG {
-        println!("Priority of {} is {}", 'p', get_item_priority('p'));
-        println!("Priority of {} is {}", 'L', get_item_priority('L'));
-        println!("Priority of {} is {}", 'P', get_item_priority('P'));
-        println!("Priority of {} is {}", 'v', get_item_priority('v'));
-        println!("Priority of {} is {}", 't', get_item_priority('t'));
-        println!("Priority of {} is {}", 's', get_item_priority('s'));
+        for item_type in ['p', 'L', 'P', 'v', 't', 's'] {
+            println!("Priority of {} is {}", item_type, scheme.priority(&item_type));
+        }
     }
 
     println!("Comparing both compartments of each rucksack for misplaced items and calculating sum of priorities...");
-    let total_misplaced_item_priority: i32 = input_string.lines().map(|line| calc_misplaced_item_priority(line)).sum();
+    let total_misplaced_item_priority: i32 = input_string.lines().map(|line| calc_misplaced_item_priority(line, &scheme)).sum();
     println!("Sum of priorities of all item types appearing in both compartments of a rucksack: {}", total_misplaced_item_priority);
 
-    println!("Parsing groups of three...");
-    let groups_of_three = parse_groups_of_three(input_string.as_str());
+    if sliding {
+        println!("Finding shared items in sliding groups of {}...", group_size);
+        let shared_items_per_window = find_shared_items_in_sliding_groups(input_string.as_str(), group_size);
+        let windows_with_shared_items = shared_items_per_window.iter().filter(|shared| !shared.is_empty()).count();
+        let total_shared_priority: i32 = shared_items_per_window.iter().flatten().map(|item_type| scheme.priority(item_type)).sum();
+        println!("{} of {} sliding groups share at least one item type", windows_with_shared_items, shared_items_per_window.len());
+        println!("Sum of priorities of all item types shared within a sliding group: {}", total_shared_priority);
+        return;
+    }
+
+    println!("Parsing groups of {}...", group_size);
+    let groups = match parse_groups(input_string.as_str(), group_size) {
+        Err(error) => panic!("Invalid input! {}", error),
+        Ok(groups) => groups,
+    };
     println!("Finding badges and calculating sum of badge item priorities...");
-    let total_badge_priority: i32 = groups_of_three.iter().map(|group| calc_badge_priority(group)).sum();
+    let total_badge_priority: i32 = groups.iter().map(|group| calc_badge_priority(group, &scheme)).sum();
     println!("Sum of all badge item priorities: {}", total_badge_priority);
 
     /********************************************************************************************************
@@ -35,31 +142,87 @@ fn main() {
      * Successfully read ./src/input.txt
      * Comparing both compartments of each rucksack for misplaced items and calculating sum of priorities...
      * Sum of priorities of all item types appearing in both compartments of a rucksack: 7716
-     * Parsing groups of three...
+     * Parsing groups of 3...
      * Finding badges and calculating sum of badge item priorities...
      * Sum of all badge item priorities: 2973
      ********************************************************************************************************/
 }
 
-fn calc_misplaced_item_priority(rucksack_line: &str) -> i32 {
+impl PriorityScheme {
+    fn letters() -> PriorityScheme {
+        PriorityScheme::from_alphabet("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ")
+    }
+
+    // items are prioritized by their position in the alphabet, starting with 1
+    fn from_alphabet(alphabet: &str) -> PriorityScheme {
+        let priorities = alphabet.chars().enumerate().map(|(index, item_type)| (item_type, (index + 1) as i32)).collect();
+        PriorityScheme { priorities }
+    }
+
+    // each line of the weight file assigns a priority to an item type, e.g. "a 1"
+    fn from_weight_file(path: &Path) -> Result<PriorityScheme, String> {
+        let weights = std::fs::read_to_string(path).map_err(|error| format!("Failed to read {} - error: {}", path.display(), error))?;
+        let mut priorities = HashMap::new();
+        for (line_index, line) in weights.lines().enumerate().filter(|(_i, line)| !line.trim().is_empty()) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let item_type = match parts.as_slice() {
+                [item, _weight] if item.chars().count() == 1 => item.chars().next().unwrap(),
+                _ => return Err(format!("Line {}: expected \"<item> <weight>\" but got {:?}", line_index + 1, line)),
+            };
+            let weight = parts[1].parse::<i32>().map_err(|error| format!("Line {}: invalid weight {:?} - error: {}", line_index + 1, parts[1], error))?;
+            if priorities.insert(item_type, weight).is_some() {
+                return Err(format!("Line {}: duplicate weight for item type {:?}", line_index + 1, item_type));
+            }
+        }
+        Ok(PriorityScheme { priorities })
+    }
+
+    fn from_name(name: &str) -> Result<PriorityScheme, String> {
+        match name {
+            "letters" => Ok(PriorityScheme::letters()),
+            "digits" => Ok(PriorityScheme::from_alphabet("0123456789")),
+            "ascii" => Ok(PriorityScheme::from_alphabet(&('!'..='~').collect::<String>())),
+            "extended" => Ok(PriorityScheme::from_alphabet(&('!'..='~').chain('¡'..='ÿ').collect::<String>())),
+            _ => {
+                if let Some(alphabet) = name.strip_prefix("alphabet:") {
+                    Ok(PriorityScheme::from_alphabet(alphabet))
+                } else if let Some(path) = name.strip_prefix("weights:") {
+                    PriorityScheme::from_weight_file(Path::new(path))
+                } else {
+                    Err("Unknown scheme".to_string())
+                }
+            }
+        }
+    }
+
+    fn contains(&self, item_type: &char) -> bool {
+        self.priorities.contains_key(item_type)
+    }
+
+    fn priority(&self, item_type: &char) -> i32 {
+        *self.priorities.get(item_type).unwrap_or_else(|| panic!("Invalid item type {}", item_type))
+    }
+}
+
+fn calc_misplaced_item_priority(rucksack_line: &str, scheme: &PriorityScheme) -> i32 {
     let compartments = parse_rucksack_compartments(rucksack_line);
     let item_types = get_item_types_appearing_in_both_compartments(compartments);
-    let priorities: i32 = item_types.iter().map(|i| get_item_priority(i)).sum();
-    return priorities;
+    let priorities: i32 = item_types.iter().map(|item_type| scheme.priority(item_type)).sum();
+    priorities
 }
 
 fn parse_rucksack_compartments(rucksack_line: &str) -> (&str, &str) {
-    if rucksack_line.len() % 2 != 0 {
-        panic!("Invalid input! Uneven length for rucksack {}", rucksack_line);
-    }
-    let mid = rucksack_line.len() / 2;
-    rucksack_line.split_at(mid)
+    split_compartments(rucksack_line).unwrap_or_else(|| panic!("Invalid input! Uneven length for rucksack {}", rucksack_line))
 }
 
-fn get_item_priority(item_type: &char) -> i32 {
-    let letters = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
-    let priority = letters.find(*item_type).unwrap_or_else(|| panic!("Invalid item type {}", item_type));
-    (priority + 1) as i32
+fn split_compartments(rucksack_line: &str) -> Option<(&str, &str)> {
+    // split by item count rather than bytes, items may be multi-byte characters
+    let item_count = rucksack_line.chars().count();
+    if !item_count.is_multiple_of(2) {
+        return None;
+    }
+    let mid = rucksack_line.char_indices().nth(item_count / 2).map_or(rucksack_line.len(), |(index, _item_type)| index);
+    Some(rucksack_line.split_at(mid))
 }
 
 fn get_item_types_appearing_in_both_compartments(compartments: (&str, &str)) -> HashSet<char> {
@@ -72,41 +235,463 @@ fn get_item_types_appearing_in_both_compartments(compartments: (&str, &str)) ->
     appearing_in_both
 }
 
-fn parse_groups_of_three(input_string: &str) -> Vec<Vec<String>> {
-    let mut groups_of_three: Vec<Vec<String>> = Vec::new();
-    for (line_index, rucksack) in input_string.lines().enumerate() {
-        // start a new group every 3rd line
-        if line_index % 3 == 0 {
-            let rucksacks = vec![rucksack.to_string()];
-            groups_of_three.push(rucksacks);
-        }
-        else {
-            let mut rucksacks = groups_of_three.pop().unwrap();
-            rucksacks.push(rucksack.to_string());
-            groups_of_three.push(rucksacks);
+fn parse_groups(input_string: &str, group_size: usize) -> Result<Vec<Vec<String>>, String> {
+    if group_size == 0 {
+        return Err("Group size must be at least 1".to_string());
+    }
+    let rucksacks: Vec<String> = input_string.lines().map(|line| line.to_string()).collect();
+    let groups: Vec<Vec<String>> = rucksacks.chunks(group_size).map(|chunk| chunk.to_vec()).collect();
+    if let Some(last_group) = groups.last() {
+        if last_group.len() < group_size {
+            return Err(format!(
+                "Incomplete trailing group of {} rucksacks starting at line {}, expected groups of {}",
+                last_group.len(),
+                rucksacks.len() - last_group.len() + 1,
+                group_size
+            ));
         }
     }
-    groups_of_three
+    Ok(groups)
+}
+
+fn find_shared_item_types(rucksacks: &[String]) -> HashSet<char> {
+    let mut shared: HashSet<char> = match rucksacks.first() {
+        Some(first_rucksack) => first_rucksack.chars().collect(),
+        None => return HashSet::new(),
+    };
+    for rucksack in &rucksacks[1..] {
+        // intersect with each further rucksack of the group
+        shared.retain(|item_type| rucksack.contains(*item_type));
+    }
+    shared
+}
+
+fn find_shared_items_in_sliding_groups(input_string: &str, group_size: usize) -> Vec<HashSet<char>> {
+    let rucksacks: Vec<String> = input_string.lines().map(|line| line.to_string()).collect();
+    if group_size == 0 || rucksacks.len() < group_size {
+        return Vec::new();
+    }
+    let shared_items_per_window: Vec<HashSet<char>> = rucksacks.windows(group_size).map(find_shared_item_types).collect();
+    if _DEBUG {
+        println!("{:#?}", shared_items_per_window);
+    }
+    shared_items_per_window
 }
 
-fn calc_badge_priority(group_of_three: &Vec<String>) -> i32 {
-    let first_rucksack = &group_of_three[0];
-    let second_rucksack = &group_of_three[1];
-    let third_rucksack = &group_of_three[2];
-    let badge: HashSet<char> = first_rucksack.chars().filter(|i| second_rucksack.contains(*i) && third_rucksack.contains(*i)).collect();
+fn calc_badge_priority(group: &[String], scheme: &PriorityScheme) -> i32 {
+    let badge = find_shared_item_types(group);
     if badge.is_empty() {
-        panic!("Invalid input! Could not detect a badge in a group of three rucksacks!");
+        panic!("Invalid input! Could not detect a badge in a group of {} rucksacks!", group.len());
     }
     if badge.len() > 1 {
-        panic!("Invalid input! Found more than one badge in a group of three rucksacks! {:?}", badge);
+        panic!("Invalid input! Found more than one badge in a group of {} rucksacks! {:?}", group.len(), badge);
+    }
+    scheme.priority(&badge.into_iter().collect::<Vec<char>>()[0])
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::UnevenLength { line, length } => write!(f, "Line {}: rucksack has uneven length {}", line, length),
+            Violation::InvalidItemType { line, item_type } => write!(f, "Line {}: invalid item type {:?}", line, item_type),
+            Violation::NoMisplacedItem { line } => write!(f, "Line {}: no item type appears in both compartments", line),
+            Violation::MultipleMisplacedItems { line, item_types } => write!(f, "Line {}: more than one item type appears in both compartments {:?}", line, item_types),
+            Violation::IncompleteGroup { first_line, size } => write!(f, "Line {}: incomplete trailing group of {} rucksacks", first_line, size),
+            Violation::NoBadge { first_line } => write!(f, "Line {}: group has no badge", first_line),
+            Violation::MultipleBadges { first_line, badges } => write!(f, "Line {}: group has more than one badge {:?}", first_line, badges),
+        }
+    }
+}
+
+impl Violation {
+    fn line(&self) -> usize {
+        match self {
+            Violation::UnevenLength { line, .. } | Violation::InvalidItemType { line, .. } | Violation::NoMisplacedItem { line } | Violation::MultipleMisplacedItems { line, .. } => *line,
+            Violation::IncompleteGroup { first_line, .. } | Violation::NoBadge { first_line } | Violation::MultipleBadges { first_line, .. } => *first_line,
+        }
+    }
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let target = match self.target {
+            RepairTarget::Rucksack { line } => format!("Line {}: rucksack", line),
+            RepairTarget::Group { first_line } => format!("Line {}: group", first_line),
+        };
+        match &self.swaps {
+            Ok(swaps) => write!(f, "{} needs {} item swaps", target, swaps),
+            Err(reason) => write!(f, "{} {}", target, reason),
+        }
+    }
+}
+
+fn validate_rucksacks(input_string: &str, scheme: &PriorityScheme) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (line_index, rucksack) in input_string.lines().enumerate() {
+        let line = line_index + 1;
+        if let Some(item_type) = rucksack.chars().find(|item_type| !scheme.contains(item_type)) {
+            violations.push(Violation::InvalidItemType { line, item_type });
+            continue;
+        }
+        let compartments = match split_compartments(rucksack) {
+            Some(compartments) => compartments,
+            None => {
+                violations.push(Violation::UnevenLength { line, length: rucksack.chars().count() });
+                continue;
+            }
+        };
+        let item_types: BTreeSet<char> = get_item_types_appearing_in_both_compartments(compartments).into_iter().collect();
+        if item_types.is_empty() {
+            violations.push(Violation::NoMisplacedItem { line });
+        } else if item_types.len() > 1 {
+            violations.push(Violation::MultipleMisplacedItems { line, item_types: item_types.into_iter().collect() });
+        }
+    }
+    violations
+}
+
+fn validate_groups(input_string: &str, group_size: usize) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let rucksacks: Vec<String> = input_string.lines().map(|line| line.to_string()).collect();
+    for (group_index, group) in rucksacks.chunks(group_size).enumerate() {
+        let first_line = group_index * group_size + 1;
+        if group.len() < group_size {
+            violations.push(Violation::IncompleteGroup { first_line, size: group.len() });
+            continue;
+        }
+        let badges: BTreeSet<char> = find_shared_item_types(group).into_iter().collect();
+        if badges.is_empty() {
+            violations.push(Violation::NoBadge { first_line });
+        } else if badges.len() > 1 {
+            violations.push(Violation::MultipleBadges { first_line, badges: badges.into_iter().collect() });
+        }
+    }
+    violations
+}
+
+fn count_item_types(items: &str) -> HashMap<char, usize> {
+    let mut counts = HashMap::new();
+    for item_type in items.chars() {
+        *counts.entry(item_type).or_insert(0) += 1;
+    }
+    counts
+}
+
+// A swap exchanges one item of the first compartment with one item of the second one, so both keep half of
+// the rucksack. Any rearrangement into equal halves is reachable, swapping out as many items of the first
+// compartment as it has to give up. Exactly one item type t may end up in both halves, every other type has
+// to end up entirely in one of them. Returns the number of swaps and the repaired rucksack.
+fn repair_rucksack(rucksack: &str) -> Option<(usize, String)> {
+    let (compartment1, compartment2) = split_compartments(rucksack)?;
+    let half = compartment1.chars().count();
+    let in_first = count_item_types(compartment1);
+    let totals = count_item_types(rucksack);
+    let mut item_types: Vec<char> = totals.keys().copied().collect();
+    item_types.sort_unstable();
+
+    let mut best: Option<(usize, char, Vec<char>)> = None; // swaps, shared item type, types kept entirely in the first half
+    for shared in item_types.iter().copied().filter(|item_type| totals[item_type] >= 2) {
+        let others: Vec<char> = item_types.iter().copied().filter(|item_type| *item_type != shared).collect();
+        // knapsack over the other types: most items of the first compartment that can stay, by size of the first half
+        let mut kept: Vec<Vec<Option<usize>>> = vec![vec![None; half + 1]; others.len() + 1];
+        kept[0][0] = Some(0);
+        for (index, item_type) in others.iter().enumerate() {
+            for size in 0..=half {
+                let skip = kept[index][size];
+                let take = size.checked_sub(totals[item_type]).and_then(|rest| kept[index][rest]).map(|stay| stay + in_first.get(item_type).unwrap_or(&0));
+                kept[index + 1][size] = skip.max(take);
+            }
+        }
+        let first_total: usize = others.iter().map(|item_type| in_first.get(item_type).unwrap_or(&0)).sum();
+        for size in 0..half {
+            let (shared_in_first, stay) = match kept[others.len()][size] {
+                Some(stay) if half - size < totals[&shared] => (half - size, stay),
+                _ => continue,
+            };
+            let swaps = first_total - stay + in_first.get(&shared).unwrap_or(&0).saturating_sub(shared_in_first);
+            if best.as_ref().is_none_or(|(best_swaps, _shared, _kept)| swaps < *best_swaps) {
+                // trace back which types fill the first half
+                let mut kept_types = vec![];
+                let mut rest = size;
+                for index in (0..others.len()).rev() {
+                    if kept[index + 1][rest] != kept[index][rest] {
+                        kept_types.push(others[index]);
+                        rest -= totals[&others[index]];
+                    }
+                }
+                best = Some((swaps, shared, kept_types));
+            }
+        }
+    }
+
+    let (swaps, shared, kept_types) = best?;
+    let mut wanted: HashMap<char, usize> = kept_types.iter().map(|item_type| (*item_type, totals[item_type])).collect();
+    wanted.insert(shared, half - kept_types.iter().map(|item_type| totals[item_type]).sum::<usize>());
+    let mut first: Vec<char> = compartment1.chars().collect();
+    let mut second: Vec<char> = compartment2.chars().collect();
+    let mut missing = wanted.clone();
+    for item_type in &first {
+        if let Some(count) = missing.get_mut(item_type).filter(|count| **count > 0) {
+            *count -= 1;
+        }
+    }
+    let mut excess: HashMap<char, usize> = in_first.iter().map(|(item_type, count)| (*item_type, count.saturating_sub(*wanted.get(item_type).unwrap_or(&0)))).collect();
+    let outgoing: Vec<usize> = (0..first.len())
+        .filter(|index| excess.get_mut(&first[*index]).filter(|count| **count > 0).map(|count| *count -= 1).is_some())
+        .collect();
+    let incoming: Vec<usize> = (0..second.len())
+        .filter(|index| missing.get_mut(&second[*index]).filter(|count| **count > 0).map(|count| *count -= 1).is_some())
+        .collect();
+    for (out, into) in outgoing.iter().zip(&incoming) {
+        std::mem::swap(&mut first[*out], &mut second[*into]);
+    }
+    let repaired: String = first.iter().chain(&second).collect();
+
+    // the repaired rucksack has to pass the same check the puzzle uses
+    let shared_after = get_item_types_appearing_in_both_compartments(split_compartments(&repaired).unwrap());
+    assert_eq!((swaps, swaps, HashSet::from([shared])), (outgoing.len(), incoming.len(), shared_after));
+    Some((swaps, repaired))
+}
+
+fn calc_rucksack_repair_swaps(rucksack: &str) -> Option<usize> {
+    repair_rucksack(rucksack).map(|(swaps, _repaired)| swaps)
+}
+
+// item types present in every rucksack of the group
+fn find_badges(group: &[Vec<char>]) -> BTreeSet<char> {
+    let mut badges: BTreeSet<char> = group.first().map(|rucksack| rucksack.iter().copied().collect()).unwrap_or_default();
+    for rucksack in &group[1..] {
+        badges.retain(|item_type| rucksack.contains(item_type));
+    }
+    badges
+}
+
+// Every badge but the kept one has to leave one rucksack of the group entirely. Two copies leaving different
+// rucksacks can trade places in a single swap, so a purge takes max(ceil(copies / 2), most copies of one rucksack).
+// Returns that or the given bound, whichever is lower. Choosing the rucksacks counts against the search steps,
+// once those run out every badge is assumed to leave the rucksack with its fewest copies, never overestimating.
+fn calc_purge_swaps(counts: &[HashMap<char, usize>], purged: &[char], bound: usize, steps: &mut usize) -> usize {
+    // false if the steps ran out before every assignment was either tried or ruled out
+    fn search(copies: &[Vec<(usize, usize)>], fewest_left: &[usize], loads: &mut Vec<usize>, assigned: usize, best: &mut usize, steps: &mut usize) -> bool {
+        *steps += 1;
+        if *steps >= MAX_GROUP_REPAIR_STEPS {
+            return false;
+        }
+        let swaps = (loads.iter().sum::<usize>() + fewest_left[assigned]).div_ceil(2).max(*loads.iter().max().unwrap());
+        if swaps >= *best || assigned == copies.len() {
+            *best = swaps.min(*best);
+            return true;
+        }
+        for (rucksack, count) in &copies[assigned] {
+            loads[*rucksack] += count;
+            let completed = search(copies, fewest_left, loads, assigned + 1, best, steps);
+            loads[*rucksack] -= count;
+            if !completed {
+                return false;
+            }
+        }
+        true
+    }
+    // copies of each badge per rucksack, fewest first, for the badges with the most copies to leave first
+    let mut copies: Vec<Vec<(usize, usize)>> = purged
+        .iter()
+        .map(|badge| {
+            let mut copies: Vec<(usize, usize)> = counts.iter().map(|c| c[badge]).enumerate().collect();
+            copies.sort_by_key(|(_rucksack, count)| *count);
+            copies
+        })
+        .collect();
+    copies.sort_by_key(|copies| std::cmp::Reverse(copies[0].1));
+    // copies of the badges from an index onwards, if each of them leaves the rucksack with its fewest copies
+    let fewest_left: Vec<usize> = (0..=copies.len()).map(|assigned| copies[assigned..].iter().map(|copies| copies[0].1).sum()).collect();
+    let mut best = bound;
+    match search(&copies, &fewest_left, &mut vec![0; counts.len()], 0, &mut best, steps) {
+        true => best,
+        false => fewest_left[0].div_ceil(2).max(copies.first().map_or(0, |copies| copies[0].1)).min(bound),
     }
-    let badge_priority = get_item_priority(badge.into_iter().collect::<Vec<char>>()[0]);
-    badge_priority
+}
+
+// moves one item of a type between the counts of two rucksacks, forgetting types that are gone
+fn move_item_count(counts: &mut [HashMap<char, usize>], item_type: char, from: usize, to: usize) {
+    let left = counts[from].get_mut(&item_type).unwrap();
+    *left -= 1;
+    if *left == 0 {
+        counts[from].remove(&item_type);
+    }
+    *counts[to].entry(item_type).or_insert(0) += 1;
+}
+
+// Lower bound of the swaps needed for exactly one badge, ignoring whether the rucksacks stay valid. Without a
+// badge, every rucksack lacking the new one needs a swap. None if no amount of swaps gets there.
+fn estimate_group_swaps(counts: &[HashMap<char, usize>], steps: &mut usize) -> Option<usize> {
+    let badges: Vec<char> = counts.first()?.keys().copied().filter(|item_type| counts[1..].iter().all(|c| c.contains_key(item_type))).collect();
+    if badges.len() == 1 {
+        return Some(0);
+    }
+    if counts.len() < 2 {
+        return None;
+    }
+    if badges.is_empty() {
+        let mut totals: HashMap<char, usize> = HashMap::new();
+        for (item_type, count) in counts.iter().flatten() {
+            *totals.entry(*item_type).or_insert(0) += count;
+        }
+        return totals
+            .iter()
+            .filter(|(_item_type, total)| **total >= counts.len())
+            .map(|(item_type, _total)| counts.iter().filter(|c| !c.contains_key(item_type)).count())
+            .min();
+    }
+    let purge = |bound: usize, kept: &char| calc_purge_swaps(counts, &badges.iter().copied().filter(|badge| badge != kept).collect::<Vec<char>>(), bound, steps);
+    Some(badges.iter().fold(usize::MAX, purge))
+}
+
+fn has_one_misplaced_item(rucksack: &[char]) -> bool {
+    let (first, second) = rucksack.split_at(rucksack.len() / 2);
+    rucksack.len().is_multiple_of(2) && first.iter().filter(|item_type| second.contains(item_type)).collect::<HashSet<_>>().len() == 1
+}
+
+fn find_broken_rucksacks(group: &[Vec<char>], scheme: &PriorityScheme) -> HashSet<usize> {
+    let rucksacks: Vec<String> = group.iter().map(|rucksack| rucksack.iter().collect()).collect();
+    validate_rucksacks(&rucksacks.join("\n"), scheme).iter().map(Violation::line).collect()
+}
+
+// the order of items within a compartment does not matter for any of the checks
+fn group_state_key(group: &[Vec<char>]) -> Vec<Vec<char>> {
+    group
+        .iter()
+        .flat_map(|rucksack| {
+            let (first, second) = rucksack.split_at(rucksack.len() / 2);
+            [first, second].map(|compartment| {
+                let mut sorted = compartment.to_vec();
+                sorted.sort_unstable();
+                sorted
+            })
+        })
+        .collect()
+}
+
+// Iterative deepening over swaps, cut off as soon as the estimate shows the limit cannot be kept. Promising swaps
+// that keep valid rucksacks valid are tried first, states already searched with as many swaps left are skipped.
+fn search_group_swaps(group: &mut [Vec<char>], search: &mut GroupRepairSearch, swaps: usize) -> bool {
+    if find_badges(group).len() == 1 && find_broken_rucksacks(group, search.scheme).is_subset(&search.broken) {
+        return true;
+    }
+    let left = search.limit - swaps;
+    if left == 0 || search.steps >= MAX_GROUP_REPAIR_STEPS || search.searched.get(&group_state_key(group)).is_some_and(|searched| *searched >= left) {
+        return false;
+    }
+    search.searched.insert(group_state_key(group), left);
+
+    let mut candidates = vec![]; // estimate, rucksacks newly broken, the swap
+    for first in 0..group.len() {
+        for second in first + 1..group.len() {
+            // swapping the same item types between the same compartments leads to the same outcome
+            let mut tried = HashSet::new();
+            for index1 in 0..group[first].len() {
+                for index2 in 0..group[second].len() {
+                    let (item1, item2) = (group[first][index1], group[second][index2]);
+                    let sides = (2 * index1 >= group[first].len(), 2 * index2 >= group[second].len());
+                    if item1 == item2 || !tried.insert((item1, item2, sides)) {
+                        continue;
+                    }
+                    search.steps += 1;
+                    if search.steps >= MAX_GROUP_REPAIR_STEPS {
+                        return false;
+                    }
+                    move_item_count(&mut search.counts, item1, first, second);
+                    move_item_count(&mut search.counts, item2, second, first);
+                    group[first][index1] = item2;
+                    group[second][index2] = item1;
+                    if let Some(estimate) = estimate_group_swaps(&search.counts, &mut search.steps).filter(|estimate| *estimate < left) {
+                        let newly_broken = [first, second].iter().filter(|r| !search.broken.contains(&(**r + 1)) && !has_one_misplaced_item(&group[**r])).count();
+                        candidates.push((estimate, newly_broken, first, index1, second, index2));
+                    }
+                    group[first][index1] = item1;
+                    group[second][index2] = item2;
+                    move_item_count(&mut search.counts, item1, second, first);
+                    move_item_count(&mut search.counts, item2, first, second);
+                }
+            }
+        }
+    }
+    candidates.sort_unstable();
+    for (_estimate, _newly_broken, first, index1, second, index2) in candidates {
+        let (item1, item2) = (group[first][index1], group[second][index2]);
+        move_item_count(&mut search.counts, item1, first, second);
+        move_item_count(&mut search.counts, item2, second, first);
+        group[first][index1] = item2;
+        group[second][index2] = item1;
+        if search_group_swaps(group, search, swaps + 1) {
+            return true;
+        }
+        group[first][index1] = item1;
+        group[second][index2] = item2;
+        move_item_count(&mut search.counts, item1, second, first);
+        move_item_count(&mut search.counts, item2, first, second);
+    }
+    false
+}
+
+// A swap exchanges one item of a rucksack with one item of another rucksack of the group, so every rucksack
+// keeps its length. Exactly one badge has to remain, and rucksacks that were valid on their own have to stay
+// valid. Returns the fewest swaps and the repaired group. Repairs longer than the estimate by more than a few
+// swaps or beyond the step limit of the search are not found, the error tells those apart from impossible ones.
+fn repair_group(group: &[String], scheme: &PriorityScheme) -> Result<(usize, Vec<String>), String> {
+    let mut repaired: Vec<Vec<char>> = group.iter().map(|rucksack| rucksack.chars().collect()).collect();
+    let counts: Vec<HashMap<char, usize>> = group.iter().map(|rucksack| count_item_types(rucksack)).collect();
+    let mut steps = 0;
+    let estimate = estimate_group_swaps(&counts, &mut steps).ok_or("cannot be repaired by swapping items")?;
+    let broken = find_broken_rucksacks(&repaired, scheme);
+    let mut search = GroupRepairSearch { scheme, counts, broken, limit: estimate, steps, searched: HashMap::new() };
+    let swaps = (estimate..=estimate + MAX_EXTRA_GROUP_REPAIR_SWAPS).find(|limit| {
+        search.limit = *limit;
+        search.searched.clear();
+        search_group_swaps(&mut repaired, &mut search, 0)
+    });
+    let swaps = match swaps {
+        Some(swaps) => swaps,
+        None if search.steps >= MAX_GROUP_REPAIR_STEPS => return Err(format!("has no repair found within {} search steps", MAX_GROUP_REPAIR_STEPS)),
+        None => return Err(format!("has no repair of up to {} item swaps", estimate + MAX_EXTRA_GROUP_REPAIR_SWAPS)),
+    };
+    Ok((swaps, repaired.iter().map(|rucksack| rucksack.iter().collect()).collect()))
+}
+
+fn calc_group_repair_swaps(group: &[String], scheme: &PriorityScheme) -> Result<usize, String> {
+    let (swaps, repaired) = repair_group(group, scheme)?;
+    // the repaired group has to pass the same checks as the input
+    let items: Vec<Vec<char>> = repaired.iter().map(|rucksack| rucksack.chars().collect()).collect();
+    assert_eq!(1, find_badges(&items).len());
+    let broken_lines = |rucksacks: &[String]| -> HashSet<usize> { validate_rucksacks(&rucksacks.join("\n"), scheme).iter().map(Violation::line).collect() };
+    assert!(broken_lines(&repaired).is_subset(&broken_lines(group)));
+    Ok(swaps)
+}
+
+fn plan_rucksack_repairs(input_string: &str) -> Vec<Repair> {
+    input_string
+        .lines()
+        .enumerate()
+        .map(|(line_index, rucksack)| Repair { target: RepairTarget::Rucksack { line: line_index + 1 }, swaps: calc_rucksack_repair_swaps(rucksack).ok_or("cannot be repaired by swapping items".to_string()) })
+        .filter(|repair| repair.swaps != Ok(0))
+        .collect()
+}
+
+fn plan_group_repairs(input_string: &str, group_size: usize, scheme: &PriorityScheme) -> Vec<Repair> {
+    let rucksacks: Vec<String> = input_string.lines().map(|line| line.to_string()).collect();
+    rucksacks
+        .chunks(group_size)
+        .enumerate()
+        .map(|(group_index, group)| {
+            let swaps = if group.len() < group_size { Err("is incomplete".to_string()) } else { calc_group_repair_swaps(group, scheme) };
+            Repair { target: RepairTarget::Group { first_line: group_index * group_size + 1 }, swaps }
+        })
+        .filter(|repair| repair.swaps != Ok(0))
+        .collect()
 }
 
 fn read_input() -> String {
     let input_path = Path::new("./src/input.txt");
-    let mut input_file = match File::open(&input_path) {
+    let mut input_file = match File::open(input_path) {
         Err(error) => panic!("Failed to open {} - error: {}", input_path.display(), error),
         Ok(file) => file,
     };
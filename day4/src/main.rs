@@ -3,62 +3,617 @@
  * https://adventofcode.com/2022/day/4
  ********************************************/
 
-use std::{path::Path, fs::File, io::Read, ops::Range};
+use std::{path::Path, fs::File, io::Read, env, collections::BTreeSet, time::Instant};
 
 const _DEBUG: bool = false;
 
+// closed interval of sections [start, end]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+// member of the group (1-based) on the given input line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Elf {
+    line: usize,
+    member: usize,
+}
+
+// static interval tree: assignments sorted by start, implicitly forming a balanced tree
+// where each node (the middle of its range) stores the maximum end and length within its subtree
+#[derive(Debug)]
+struct IntervalIndex {
+    entries: Vec<(Interval<i64>, Elf)>,
+    max_end: Vec<i64>,
+    max_len: Vec<u128>,
+}
+
+// maps sections onto terminal columns, each column standing for the same number of sections
+#[derive(Debug)]
+struct StripLayout {
+    origin: i64,
+    sections_per_column: i64,
+    columns: usize,
+}
+
+const DEFAULT_RENDER_WIDTH: usize = 100;
+const ANSI_OVERLAP: &str = "\x1b[1;31m";
+const ANSI_RESET: &str = "\x1b[0m";
+
 fn main() {
+    // usage: day4 [--groups] [--sections=<start>-<end>]
+    //        day4 query <cover <section> | overlap <start> <end> | longest <section>> [--generate=<groups>]
+    //        day4 render [--line=<line>] [--width=<columns>] [--color] [--svg=<file>]
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("query") => return run_query(&args[1..]),
+        Some("render") => return run_render(&args[1..]),
+        _ => (),
+    }
+    let report_groups = args.iter().any(|arg| arg == "--groups");
+
+    // sanity checks on the example pairs and on assignments too wide to iterate
+    assert!(Interval::new(2, 8).contains(&Interval::new(3, 7)));
+    assert!(!Interval::new(2, 4).overlaps(&Interval::new(6, 8)));
+    assert_eq!(Some(Interval::new(7, 7)), Interval::new(5, 7).intersection(&Interval::new(7, 9)));
+    assert_eq!(Some(Interval::new(2, 8)), Interval::new(2, 4).union(&Interval::new(5, 8)));
+    assert_eq!(None, Interval::new(2, 3).union(&Interval::new(5, 8)));
+    assert_eq!(5_000_000_001, Interval::new(0u64, 5_000_000_000).len());
+    assert!(Interval::new(i64::MIN, i64::MAX).contains_section(0));
+    let merged = [Interval::new(3, 4), Interval::new(7, 8)];
+    assert_eq!(vec![Interval::new(1, 2), Interval::new(5, 6)], find_uncovered_sections(&merged, &calc_section_range(merged.iter())));
+    assert_eq!(vec![Interval::new(5, 6), Interval::new(9, 10)], find_uncovered_sections(&merged, &Interval::new(4, 10)));
+    assert_eq!(Vec::<Interval<i64>>::new(), find_uncovered_sections(&merged, &Interval::new(7, 7)));
+    assert_eq!(vec![Interval::new(5, 6)], find_uncovered_sections(&merged, &Interval::new(5, 6)));
+
     println!("Reading input file...");
     let input_string = read_input();
-    println!("Read {} pairs. Parsing ranges and calculating overlaps...", input_string.lines().count());
+    // skipping subtrees has to find the same assignment as looking at every one covering the section
+    let index = IntervalIndex::build(&input_string.lines().map(parse_ranges).collect::<Vec<_>>());
+    for section in 0..=100 {
+        let covering = index.find_overlapping(&Interval::new(section, section));
+        let longest = covering.into_iter().max_by_key(|(assignment, elf)| (assignment.len(), std::cmp::Reverse(*elf)));
+        assert_eq!(longest, index.find_longest_covering(section));
+    }
+    println!("Read {} groups. Parsing ranges and calculating overlaps...", input_string.lines().count());
     let mut fully_contained = 0;
     let mut overlaps = 0;
-    for line in input_string.lines() {
-        let (first_range, second_range) = parse_ranges(&line);
-        if is_fully_contained_in(&first_range, &second_range) || is_fully_contained_in(&second_range, &first_range) {
+    let mut contiguous = 0;
+    let mut overlapping_sections = 0;
+    let mut assignments: Vec<Interval<i64>> = Vec::new();
+    for (line_index, line) in input_string.lines().enumerate() {
+        let group = parse_ranges(line);
+        let overlap_matrix = calc_overlap_matrix(&group);
+        if has_member_containing_another(&group) {
             fully_contained += 1;
         }
-        if is_overlapping_with(&first_range, &second_range) {
+        if overlap_matrix.iter().enumerate().any(|(i, row)| row.iter().enumerate().any(|(j, overlapping)| i != j && *overlapping)) {
             overlaps +=1;
         }
+        if covers_contiguous_span(&group) {
+            contiguous += 1;
+        }
+        overlapping_sections += calc_sections_assigned_more_than_once(&group);
+        if report_groups {
+            print_group_report(line_index + 1, &group, &overlap_matrix);
+        }
+        assignments.extend(group);
     }
-    println!("Found {} pairs where one fully contains the other!", fully_contained);
-    println!("Found {} pairs where there is an overlap!", overlaps);
+    println!("Found {} groups where one fully contains another!", fully_contained);
+    println!("Found {} groups where there is an overlap!", overlaps);
+    println!("Found {} groups covering a contiguous span!", contiguous);
+    println!("Found {} sections assigned to more than one elf of a group!", overlapping_sections);
+
+    println!("Merging all {} assignments and analyzing coverage...", assignments.len());
+    let covered = merge_intervals(&assignments);
+    let sections = match args.iter().find_map(|arg| arg.strip_prefix("--sections=")) {
+        Some(range) => match parse_ranges(range)[..] {
+            [sections] => sections,
+            _ => panic!("Invalid section range {:?}, expected <start>-<end>", range),
+        },
+        None => calc_section_range(assignments.iter()),
+    };
+    let uncovered = find_uncovered_sections(&covered, &sections);
+    let uncovered_sections: u128 = uncovered.iter().map(|gap| gap.len()).sum();
+    println!("Found {} sections of {} not covered by any elf: {}", uncovered_sections, format_intervals(&[sections]), format_intervals(&uncovered));
+    let coverage_depths = calc_coverage_depths(&assignments);
+    let max_depth = coverage_depths.iter().map(|(_segment, depth)| *depth).max().unwrap_or(0);
+    let deepest: Vec<Interval<i64>> = coverage_depths.iter().filter(|(_segment, depth)| *depth == max_depth).map(|(segment, _depth)| *segment).collect();
+    println!("Maximum overlap depth of {} elves at sections: {}", max_depth, format_intervals(&merge_intervals(&deepest)));
+    let assigned_sections: u128 = assignments.iter().map(|assignment| assignment.len()).sum();
+    let covered_sections: u128 = covered.iter().map(|interval| interval.len()).sum();
+    println!("Total duplicated work: {} of {} assigned section cleanups are redundant", assigned_sections - covered_sections, assigned_sections);
 
     /***********************************************
      * Reading input file...
      * Successfully read ./src/input.txt
-     * Read 1000 pairs. Parsing ranges and calculating overlaps...
-     * Found 466 pairs where one fully contains the other!
-     * Found 865 pairs where there is an overlap!
+     * Read 1000 groups. Parsing ranges and calculating overlaps...
+     * Found 466 groups where one fully contains another!
+     * Found 865 groups where there is an overlap!
+     * Found 977 groups covering a contiguous span!
+     * Found 20488 sections assigned to more than one elf of a group!
+     * Merging all 2000 assignments and analyzing coverage...
+     * Found 0 sections of 1-99 not covered by any elf: none
+     * Maximum overlap depth of 1030 elves at sections: 46
+     * Total duplicated work: 73655 of 73754 assigned section cleanups are redundant
      ***********************************************/
 }
 
-fn is_fully_contained_in(first: &Range<i32>, second: &Range<i32>) -> bool {
-    first.clone().all(|section| second.contains(&section))
+impl<T: Copy + Ord + Into<i128>> Interval<T> {
+    fn new(start: T, end: T) -> Interval<T> {
+        if start > end {
+            panic!("Invalid interval! Start {} is after end {}", start.into(), end.into());
+        }
+        Interval { start, end }
+    }
+
+    fn len(&self) -> u128 {
+        (self.end.into() - self.start.into() + 1) as u128
+    }
+
+    fn contains_section(&self, section: T) -> bool {
+        self.start <= section && section <= self.end
+    }
+
+    fn contains(&self, other: &Interval<T>) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    fn intersection(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Some(Interval { start: self.start.max(other.start), end: self.end.min(other.end) })
+    }
+
+    // only defined if the result is again a single interval, i.e. both overlap or are adjacent
+    fn union(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        let touching = self.start.into() <= other.end.into() + 1 && other.start.into() <= self.end.into() + 1;
+        if !touching {
+            return None;
+        }
+        Some(Interval { start: self.start.min(other.start), end: self.end.max(other.end) })
+    }
+}
+
+// merges overlapping and adjacent intervals into a sorted set of disjoint intervals
+fn merge_intervals(intervals: &[Interval<i64>]) -> Vec<Interval<i64>> {
+    let mut sorted = intervals.to_vec();
+    sorted.sort_by_key(|interval| interval.start);
+    let mut merged: Vec<Interval<i64>> = Vec::new();
+    for interval in sorted {
+        match merged.last().and_then(|last| last.union(&interval)) {
+            Some(union) => *merged.last_mut().unwrap() = union,
+            None => merged.push(interval),
+        }
+    }
+    merged
+}
+
+// the puzzle numbers sections from 1, so the camp starts there unless an assignment starts earlier
+fn calc_section_range<'a>(assignments: impl Iterator<Item = &'a Interval<i64>> + Clone) -> Interval<i64> {
+    let start = assignments.clone().map(|assignment| assignment.start).min().unwrap_or(1).min(1);
+    let end = assignments.map(|assignment| assignment.end).max().unwrap_or(start).max(start);
+    Interval::new(start, end)
+}
+
+// sections within the range not covered by any interval of a merged set, including leading and trailing gaps
+fn find_uncovered_sections(merged: &[Interval<i64>], sections: &Interval<i64>) -> Vec<Interval<i64>> {
+    let mut uncovered = Vec::new();
+    let mut next = sections.start; // first section not yet known to be covered
+    for interval in merged.iter().filter(|interval| interval.overlaps(sections)) {
+        if interval.start > next {
+            uncovered.push(Interval::new(next, interval.start - 1));
+        }
+        if interval.end >= sections.end {
+            return uncovered;
+        }
+        next = next.max(interval.end + 1);
+    }
+    uncovered.push(Interval::new(next, sections.end));
+    uncovered
+}
+
+// sweep line over all start and end events, yielding segments of constant coverage depth
+fn calc_coverage_depths(intervals: &[Interval<i64>]) -> Vec<(Interval<i64>, usize)> {
+    let mut events: Vec<(i128, i64)> = Vec::new();
+    for interval in intervals {
+        events.push((interval.start as i128, 1));
+        events.push((interval.end as i128 + 1, -1));
+    }
+    events.sort();
+    let mut segments = Vec::new();
+    let mut depth: i64 = 0;
+    for (i, (position, change)) in events.iter().enumerate() {
+        depth += change;
+        let next_position = match events.get(i + 1) {
+            Some((next_position, _change)) => *next_position,
+            None => break,
+        };
+        if depth > 0 && next_position > *position {
+            segments.push((Interval::new(*position as i64, (next_position - 1) as i64), depth as usize));
+        }
+    }
+    if _DEBUG {
+        println!("{:?}", segments);
+    }
+    segments
+}
+
+fn format_intervals(intervals: &[Interval<i64>]) -> String {
+    if intervals.is_empty() {
+        return "none".to_string();
+    }
+    intervals
+        .iter()
+        .map(|interval| if interval.start == interval.end { format!("{}", interval.start) } else { format!("{}-{}", interval.start, interval.end) })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl IntervalIndex {
+    fn build(groups: &[Vec<Interval<i64>>]) -> IntervalIndex {
+        let mut entries: Vec<(Interval<i64>, Elf)> = Vec::new();
+        for (line_index, group) in groups.iter().enumerate() {
+            for (member_index, assignment) in group.iter().enumerate() {
+                entries.push((*assignment, Elf { line: line_index + 1, member: member_index + 1 }));
+            }
+        }
+        entries.sort_by_key(|(assignment, _elf)| assignment.start);
+        let mut index = IntervalIndex { max_end: vec![i64::MIN; entries.len()], max_len: vec![0; entries.len()], entries };
+        index.build_maxima(0, index.entries.len());
+        index
+    }
+
+    fn build_maxima(&mut self, lo: usize, hi: usize) -> (i64, u128) {
+        if lo >= hi {
+            return (i64::MIN, 0);
+        }
+        let mid = (lo + hi) / 2;
+        let (left_end, left_len) = self.build_maxima(lo, mid);
+        let (right_end, right_len) = self.build_maxima(mid + 1, hi);
+        let assignment = self.entries[mid].0;
+        self.max_end[mid] = assignment.end.max(left_end).max(right_end);
+        self.max_len[mid] = assignment.len().max(left_len).max(right_len);
+        (self.max_end[mid], self.max_len[mid])
+    }
+
+    // all assignments overlapping the query in O(log n + k)
+    fn find_overlapping(&self, query: &Interval<i64>) -> Vec<(Interval<i64>, Elf)> {
+        let mut found = Vec::new();
+        self.collect_overlapping(query, 0, self.entries.len(), &mut found);
+        found
+    }
+
+    fn collect_overlapping(&self, query: &Interval<i64>, lo: usize, hi: usize, found: &mut Vec<(Interval<i64>, Elf)>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        if self.max_end[mid] < query.start {
+            return; // nothing in this subtree reaches the query
+        }
+        self.collect_overlapping(query, lo, mid, found);
+        let (assignment, elf) = self.entries[mid];
+        if assignment.start > query.end {
+            return; // this and all further assignments start after the query
+        }
+        if assignment.overlaps(query) {
+            found.push((assignment, elf));
+        }
+        self.collect_overlapping(query, mid + 1, hi, found);
+    }
+
+    fn find_covering(&self, section: i64) -> Vec<Elf> {
+        let mut elves: Vec<Elf> = self.find_overlapping(&Interval::new(section, section)).into_iter().map(|(_assignment, elf)| elf).collect();
+        elves.sort();
+        elves
+    }
+
+    // longest assignment covering the section, the first elf on ties. Subtrees that cannot hold a longer assignment
+    // are skipped, yet a section covered by k assignments of the same length still takes O(log n + k).
+    fn find_longest_covering(&self, section: i64) -> Option<(Interval<i64>, Elf)> {
+        let mut longest = None;
+        self.search_longest_covering(section, 0, self.entries.len(), &mut longest);
+        longest
+    }
+
+    fn search_longest_covering(&self, section: i64, lo: usize, hi: usize, longest: &mut Option<(Interval<i64>, Elf)>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        if self.max_end[mid] < section || longest.is_some_and(|(found, _elf)| self.max_len[mid] < found.len()) {
+            return; // nothing in this subtree reaches the section or beats the longest one found
+        }
+        let (assignment, elf) = self.entries[mid];
+        if assignment.contains_section(section) && longest.is_none_or(|(found, found_elf)| (assignment.len(), std::cmp::Reverse(elf)) > (found.len(), std::cmp::Reverse(found_elf))) {
+            *longest = Some((assignment, elf));
+        }
+        // this and all further assignments start after the section otherwise
+        let mut subtrees = vec![(lo, mid)];
+        if assignment.start <= section {
+            subtrees.push((mid + 1, hi));
+        }
+        // the more promising subtree first, so that the other one is more likely to be skipped
+        subtrees.sort_by_key(|(lo, hi)| std::cmp::Reverse(if lo < hi { self.max_len[(lo + hi) / 2] } else { 0 }));
+        for (lo, hi) in subtrees {
+            self.search_longest_covering(section, lo, hi, longest);
+        }
+    }
+}
+
+// xorshift pseudo random groups of two assignments spread over a billion sections
+fn generate_groups(count: usize) -> Vec<Vec<Interval<i64>>> {
+    let mut state: u64 = 0x2022_1204;
+    let mut next = move |bound: u64| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound) as i64
+    };
+    (0..count)
+        .map(|_i| {
+            (0..2)
+                .map(|_j| {
+                    let start = next(1_000_000_000);
+                    Interval::new(start, start + next(100_000))
+                })
+                .collect()
+        })
+        .collect()
 }
 
-fn is_overlapping_with(first: &Range<i32>, second: &Range<i32>) -> bool {
-    for section in first.clone() {
-        if second.contains(&section) {
-            return true;
+fn run_query(args: &[String]) {
+    let parse_section = |index: usize| -> i64 {
+        let arg = args.get(index).unwrap_or_else(|| panic!("Missing section argument for query {:?}", args));
+        arg.parse::<i64>().unwrap_or_else(|error| panic!("Invalid section {:?} - error: {}", arg, error))
+    };
+    let groups: Vec<Vec<Interval<i64>>> = match args.iter().find_map(|arg| arg.strip_prefix("--generate=")) {
+        Some(count) => {
+            let count = count.parse::<usize>().unwrap_or_else(|error| panic!("Invalid group count {:?} - error: {}", count, error));
+            println!("Generating {} groups...", count);
+            generate_groups(count)
         }
+        None => {
+            println!("Reading input file...");
+            read_input().lines().map(parse_ranges).collect()
+        }
+    };
+
+    let build_start = Instant::now();
+    let index = IntervalIndex::build(&groups);
+    println!("Built index over {} assignments in {:?}", index.entries.len(), build_start.elapsed());
+
+    let query_start = Instant::now();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("cover") => {
+            let section = parse_section(1);
+            let elves = index.find_covering(section);
+            println!("Section {} is covered by {} elves:", section, elves.len());
+            for elf in elves {
+                println!("  elf {} of the group on line {}", elf.member, elf.line);
+            }
+        }
+        Some("overlap") => {
+            let query = Interval::new(parse_section(1), parse_section(2));
+            let lines: BTreeSet<usize> = index.find_overlapping(&query).iter().map(|(_assignment, elf)| elf.line).collect();
+            println!("{} groups overlap sections {}:", lines.len(), format_intervals(&[query]));
+            for line in lines {
+                println!("  line {}: {}", line, format_intervals(&groups[line - 1]));
+            }
+        }
+        Some("longest") => {
+            let section = parse_section(1);
+            match index.find_longest_covering(section) {
+                Some((assignment, elf)) => println!(
+                    "Longest assignment covering section {} is {} ({} sections) of elf {} of the group on line {}",
+                    section, format_intervals(&[assignment]), assignment.len(), elf.member, elf.line
+                ),
+                None => println!("No assignment covers section {}", section),
+            }
+        }
+        _ => panic!("Unknown query {:?}, expected one of cover, overlap or longest", args),
+    }
+    println!("Answered query in {:?}", query_start.elapsed());
+}
+
+fn is_fully_contained_in(first: &Interval<i64>, second: &Interval<i64>) -> bool {
+    second.contains(first)
+}
+
+fn is_overlapping_with(first: &Interval<i64>, second: &Interval<i64>) -> bool {
+    first.overlaps(second)
+}
+
+fn has_member_containing_another(group: &[Interval<i64>]) -> bool {
+    (0..group.len()).any(|i| (0..group.len()).any(|j| i != j && is_fully_contained_in(&group[j], &group[i])))
+}
+
+// matrix[i][j] is true if the assignments of member i and member j overlap
+fn calc_overlap_matrix(group: &[Interval<i64>]) -> Vec<Vec<bool>> {
+    group.iter().map(|first| group.iter().map(|second| is_overlapping_with(first, second)).collect()).collect()
+}
+
+fn covers_contiguous_span(group: &[Interval<i64>]) -> bool {
+    merge_intervals(group).len() == 1
+}
+
+fn calc_sections_assigned_more_than_once(group: &[Interval<i64>]) -> u128 {
+    find_sections_assigned_more_than_once(group).iter().map(|interval| interval.len()).sum()
+}
+
+fn find_sections_assigned_more_than_once(group: &[Interval<i64>]) -> Vec<Interval<i64>> {
+    let mut intersections = Vec::new();
+    for i in 0..group.len() {
+        for j in i + 1..group.len() {
+            if let Some(intersection) = group[i].intersection(&group[j]) {
+                intersections.push(intersection);
+            }
+        }
+    }
+    merge_intervals(&intersections)
+}
+
+impl StripLayout {
+    fn for_groups(groups: &[(usize, Vec<Interval<i64>>)], max_columns: usize) -> StripLayout {
+        let sections = calc_section_range(groups.iter().flat_map(|(_line, group)| group.iter()));
+        let origin = sections.start;
+        let span = sections.len();
+        let sections_per_column = span.div_ceil(max_columns.max(1) as u128) as i64;
+        let columns = span.div_ceil(sections_per_column as u128) as usize;
+        StripLayout { origin, sections_per_column, columns }
+    }
+
+    fn column(&self, column: usize) -> Interval<i64> {
+        let start = self.origin + column as i64 * self.sections_per_column;
+        Interval::new(start, start.saturating_add(self.sections_per_column - 1))
+    }
+}
+
+// draws an assignment like the puzzle does, e.g. ".234....." for 2-4
+fn render_strip(assignment: &Interval<i64>, overlapping: &[Interval<i64>], layout: &StripLayout, color: bool) -> String {
+    let mut strip = String::new();
+    for column in 0..layout.columns {
+        let sections = layout.column(column);
+        if !assignment.overlaps(&sections) {
+            strip.push('.');
+            continue;
+        }
+        let cell = if layout.sections_per_column == 1 {
+            char::from_digit(sections.start.rem_euclid(10) as u32, 10).unwrap()
+        } else {
+            '='
+        };
+        let is_overlap = overlapping.iter().any(|overlap| overlap.overlaps(&sections));
+        if is_overlap && color {
+            strip.push_str(&format!("{}{}{}", ANSI_OVERLAP, cell, ANSI_RESET));
+        } else {
+            strip.push(cell);
+        }
+    }
+    strip
+}
+
+fn render_group(line: usize, group: &[Interval<i64>], layout: &StripLayout, color: bool) -> String {
+    let overlapping = find_sections_assigned_more_than_once(group);
+    let mut rendered = format!("Line {}:\n", line);
+    for (i, assignment) in group.iter().enumerate() {
+        let contained = (0..group.len()).any(|j| i != j && is_fully_contained_in(assignment, &group[j]));
+        let marker = if contained { "  <- fully contained" } else { "" };
+        rendered.push_str(&format!("{}  {}{}\n", render_strip(assignment, &overlapping, layout, color), format_intervals(&[*assignment]), marker));
+    }
+    if !color && !overlapping.is_empty() {
+        // without colors, mark the overlapping columns on an extra strip
+        let overlap_strip: String = (0..layout.columns)
+            .map(|column| if overlapping.iter().any(|overlap| overlap.overlaps(&layout.column(column))) { '^' } else { ' ' })
+            .collect();
+        rendered.push_str(&format!("{}  overlap {}\n", overlap_strip, format_intervals(&overlapping)));
+    }
+    rendered
+}
+
+fn render_svg(groups: &[(usize, Vec<Interval<i64>>)], layout: &StripLayout) -> String {
+    const CELL_WIDTH: usize = 8;
+    const ROW_HEIGHT: usize = 12;
+    const LABEL_WIDTH: usize = 80;
+    let rows: usize = groups.iter().map(|(_line, group)| group.len() + 1).sum();
+    let width = LABEL_WIDTH + layout.columns * CELL_WIDTH;
+    let height = rows * ROW_HEIGHT;
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"10\">\n", width, height);
+    let x_of = |section: i64| LABEL_WIDTH + ((section - layout.origin) / layout.sections_per_column) as usize * CELL_WIDTH;
+    let width_of = |interval: &Interval<i64>| x_of(interval.end) + CELL_WIDTH - x_of(interval.start);
+    let mut y = 0;
+    for (line, group) in groups {
+        let overlapping = find_sections_assigned_more_than_once(group);
+        for (i, assignment) in group.iter().enumerate() {
+            let contained = (0..group.len()).any(|j| i != j && is_fully_contained_in(assignment, &group[j]));
+            let fill = if contained { "#e0a030" } else { "#4080c0" };
+            svg.push_str(&format!("  <text x=\"0\" y=\"{}\">{}.{} {}</text>\n", y + ROW_HEIGHT - 2, line, i + 1, format_intervals(&[*assignment])));
+            svg.push_str(&format!("  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", x_of(assignment.start), y + 1, width_of(assignment), ROW_HEIGHT - 2, fill));
+            for overlap in overlapping.iter().filter_map(|overlap| overlap.intersection(assignment)) {
+                svg.push_str(&format!("  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#d03030\"/>\n", x_of(overlap.start), y + 1, width_of(&overlap), ROW_HEIGHT - 2));
+            }
+            y += ROW_HEIGHT;
+        }
+        y += ROW_HEIGHT; // gap between groups
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn run_render(args: &[String]) {
+    let color = args.iter().any(|arg| arg == "--color");
+    let width = match args.iter().find_map(|arg| arg.strip_prefix("--width=")) {
+        Some(width) => width.parse::<usize>().unwrap_or_else(|error| panic!("Invalid width {:?} - error: {}", width, error)),
+        None => DEFAULT_RENDER_WIDTH,
+    };
+    let only_line = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--line="))
+        .map(|line| line.parse::<usize>().unwrap_or_else(|error| panic!("Invalid line {:?} - error: {}", line, error)));
+
+    println!("Reading input file...");
+    let groups: Vec<(usize, Vec<Interval<i64>>)> = read_input()
+        .lines()
+        .enumerate()
+        .map(|(line_index, line)| (line_index + 1, parse_ranges(line)))
+        .filter(|(line, _group)| only_line.is_none_or(|only_line| only_line == *line))
+        .collect();
+    if groups.is_empty() {
+        panic!("No group found on line {:?}", only_line);
+    }
+    let layout = StripLayout::for_groups(&groups, width);
+    if layout.sections_per_column > 1 {
+        println!("Each column stands for {} sections", layout.sections_per_column);
+    }
+    for (line, group) in &groups {
+        print!("{}", render_group(*line, group, &layout, color));
+    }
+
+    if let Some(svg_path) = args.iter().find_map(|arg| arg.strip_prefix("--svg=")) {
+        match std::fs::write(svg_path, render_svg(&groups, &layout)) {
+            Err(error) => panic!("Failed to write {} - error: {}", svg_path, error),
+            Ok(()) => println!("Successfully wrote {}", svg_path),
+        }
+    }
+}
+
+fn print_group_report(line: usize, group: &[Interval<i64>], overlap_matrix: &[Vec<bool>]) {
+    println!("Group on line {}: {}", line, format_intervals(group));
+    println!("  One member contains another: {}", has_member_containing_another(group));
+    println!("  Covers a contiguous span: {}", covers_contiguous_span(group));
+    println!("  Overlap matrix:");
+    for row in overlap_matrix {
+        let cells: Vec<&str> = row.iter().map(|overlapping| if *overlapping { "X" } else { "." }).collect();
+        println!("    {}", cells.join(" "));
     }
-    false
 }
 
-fn parse_ranges(line: &str) -> (Range<i32>,Range<i32>) {
-    let pair: Vec<&str> = line.split(",").collect();
-    let first: Vec<i32> = pair[0].split("-").flat_map(|a| a.parse::<i32>()).collect(); 
-    let second: Vec<i32> = pair[1].split("-").flat_map(|a| a.parse::<i32>()).collect();
-    let first_range = first[0]..first[1]+1;     // [start,end+1)
-    let second_range = second[0]..second[1]+1;  // [start,end+1)
-    (first_range, second_range)
+fn parse_ranges(line: &str) -> Vec<Interval<i64>> {
+    line.split(",")
+        .map(|assignment| {
+            let sections: Vec<i64> = assignment
+                .split("-")
+                .map(|a| a.trim().parse::<i64>().unwrap_or_else(|error| panic!("Invalid section {:?} in line {:?} - error: {}", a, line, error)))
+                .collect();
+            if sections.len() != 2 {
+                panic!("Invalid assignment {:?} in line {:?}", assignment, line);
+            }
+            Interval::new(sections[0], sections[1])  // [start,end]
+        })
+        .collect()
 }
 
 fn read_input() -> String {
     let input_path = Path::new("./src/input.txt");
-    let mut input_file = match File::open(&input_path) {
+    let mut input_file = match File::open(input_path) {
         Err(error) => panic!("Failed to open {} - error: {}", input_path.display(), error),
         Ok(file) => file,
     };
@@ -3,36 +3,294 @@
  * https://adventofcode.com/2022/day/5
  ********************************************/
 
-use std::{fs::File, io::Read, path::Path, vec};
-
-use regex::Regex;
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap}, env, fs::File, io::{self, BufRead, Read, Write}, path::Path, thread, time::Duration, vec};
 
 const _DEBUG: bool = false;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct Move {
     from: usize,
     to: usize,
-    count: usize
+    count: usize,
+    line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MoveViolation {
+    ZeroCount,
+    UnknownStack(usize),
+    Underflow,
+    CapacityExceeded(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct InvalidMove {
+    mov: Move,
+    stack_sizes: Vec<usize>, // sizes of stacks 1 to n right before the move
+    violation: MoveViolation,
+}
+
+// what a planned move sequence has to achieve, top labels of None may be anything
+#[derive(Debug)]
+enum PlanTarget {
+    Tops(Vec<Option<String>>),
+    Arrangement(Vec<Vec<String>>),
+}
+
+#[derive(Debug, PartialEq)]
+enum PlanError {
+    UnsupportedModel,
+    Unreachable,
+    LimitReached { depth: usize, states: usize },
+}
+
+// stacks with interned crate labels
+type PlanState = Vec<Vec<u16>>;
+
+#[derive(Debug, Default, PartialEq)]
+struct CraneMetrics {
+    crates_lifted: usize,
+    lifts: usize,
+    max_stack_height: usize,
+    distance: usize, // stacks the crane travels past, starting above the first stack it lifts from
+}
+
+const DEFAULT_PLAN_MAX_DEPTH: usize = 50;
+const DEFAULT_PLAN_MAX_STATES: usize = 1_000_000;
+// trades plan length for search speed, 1 would find the shortest plans for admissible distances
+const PLAN_DISTANCE_WEIGHT: usize = 3;
+
+// applied moves can be undone and redone, keeping lift numbers in sync for every model
+struct History<'a> {
+    crate_mover: &'a dyn CrateMover,
+    stacks: Vec<Vec<String>>,
+    applied: Vec<(Move, usize)>, // each move with the number of its first lift
+    undone: Vec<Move>,
+    lift_number: usize,
+}
+
+// replays a rearrangement forth and back, the step is the number of applied moves
+struct Player<'a> {
+    history: History<'a>,
+    moves: &'a [Move],
+}
+
+const ANSI_CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const ANSI_HIGHLIGHT: &str = "\x1b[1;33m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+// a block of crates the crane lifts at once from the top of one stack onto another
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Lift {
+    count: usize,
+    reversed: bool,
+}
+
+trait CrateMover {
+    fn name(&self) -> String;
+
+    // splits a move into the lifts the crane performs, lift numbers count up across all moves
+    fn lifts(&self, mov: &Move, first_lift_number: usize) -> Vec<Lift>;
+
+    // maximum number of crates a single stack can hold
+    fn stack_capacity(&self) -> Option<usize> {
+        None
+    }
+
+    // whether a move has the same effect no matter how many lifts came before
+    fn is_stateless(&self) -> bool {
+        true
+    }
+}
+
+// lifts one crate at a time
+struct CrateMover9000;
+
+// lifts all crates of a move at once
+struct CrateMover9001;
+
+// lifts at most max_crates_per_lift crates at once
+struct LimitedCrateMover {
+    max_crates_per_lift: usize,
+}
+
+// lifts all crates of a move at once, but every other lift turns the block upside down
+struct AlternatingCrateMover;
+
+// lifts like the CrateMover 9001 onto stacks holding at most capacity crates
+struct CapacityCrateMover {
+    capacity: usize,
 }
 
 fn main() {
+    // usage: day5 [--validate [--first] | --render[=<step>] | --animate [--speed=<ms per move>] [--frames=<dir>] | --rebuild=<final drawing file>
+    //        | --plan=<tops, ? for any crate> | --plan-drawing=<target drawing file> [--max-depth=<moves>] [--max-states=<states>]
+    //        | --metrics | --optimize] [model ...] with models 9000, 9001, limited:<crates per lift>, alternating or capacity:<crates per stack>
+    let args: Vec<String> = env::args().skip(1).collect();
+    let validate = args.iter().any(|arg| arg == "--validate");
+    let first_invalid_move_only = args.iter().any(|arg| arg == "--first");
+    // render the stacks after all moves, or only after the given number of moves
+    let render_step: Option<Option<usize>> = args.iter().find_map(|arg| match arg.as_str() {
+        "--render" => Some(None),
+        _ => arg.strip_prefix("--render=").map(|step| Some(step.parse::<usize>().unwrap_or_else(|error| panic!("Invalid step {:?} - error: {}", step, error)))),
+    });
+    let animate = args.iter().any(|arg| arg == "--animate");
+    let speed = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--speed="))
+        .map(|speed| Duration::from_millis(speed.parse::<u64>().unwrap_or_else(|error| panic!("Invalid speed {:?} - error: {}", speed, error))));
+    let frames_dir = args.iter().find_map(|arg| arg.strip_prefix("--frames="));
+    let rebuild_path = args.iter().find_map(|arg| arg.strip_prefix("--rebuild="));
+    let plan_target = args.iter().find_map(|arg| {
+        if let Some(tops) = arg.strip_prefix("--plan=") {
+            Some(parse_plan_tops(tops))
+        } else if let Some(path) = arg.strip_prefix("--plan-drawing=") {
+            let drawing = std::fs::read_to_string(path).unwrap_or_else(|error| panic!("Failed to read {} - error: {}", path, error));
+            Some(PlanTarget::Arrangement(parse_initial_stacks(&drawing).unwrap_or_else(|error| panic!("Invalid drawing in {}! {}", path, error))))
+        } else {
+            None
+        }
+    });
+    let parse_limit = |prefix: &str, default: usize| match args.iter().find_map(|arg| arg.strip_prefix(prefix)) {
+        Some(limit) => limit.parse::<usize>().unwrap_or_else(|error| panic!("Invalid limit {:?} - error: {}", limit, error)),
+        None => default,
+    };
+    let plan_max_depth = parse_limit("--max-depth=", DEFAULT_PLAN_MAX_DEPTH);
+    let plan_max_states = parse_limit("--max-states=", DEFAULT_PLAN_MAX_STATES);
+    let metrics = args.iter().any(|arg| arg == "--metrics");
+    let optimize = args.iter().any(|arg| arg == "--optimize");
+    let model_names: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    let crate_movers: Vec<Box<dyn CrateMover>> = if model_names.is_empty() {
+        vec![Box::new(CrateMover9000), Box::new(CrateMover9001)]
+    } else {
+        model_names.iter().map(|name| crate_mover_by_name(name).unwrap_or_else(|error| panic!("Invalid model {} - error: {}", name, error))).collect()
+    };
+
+    // sanity checks on drawings the puzzle input does not contain
+    let ragged = "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3 ";
+    assert_eq!(Ok(vec![vec![], vec!["Z".to_string(), "N".to_string()], vec!["M".to_string(), "C".to_string(), "D".to_string()], vec!["P".to_string()]]), parse_initial_stacks(ragged));
+    let wide = "                                        [ab]\n[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [K]\n 1   2   3   4   5   6   7   8   9  10  11";
+    assert_eq!(vec!["K".to_string(), "ab".to_string()], parse_initial_stacks(wide).unwrap()[11]);
+    assert!(parse_initial_stacks("[A]\n    [B]\n 1   2 ").is_err());
+    assert!(parse_initial_stacks("  [A]\n 1   2 ").is_err());
+    assert_eq!(format!("{}\n", ragged), render_stacks(&parse_initial_stacks(ragged).unwrap()));
+    let wide_stacks = parse_initial_stacks(wide).unwrap();
+    assert_eq!(Ok(wide_stacks.clone()), parse_initial_stacks(&render_stacks(&wide_stacks)));
+    let redundant_moves = parse_moves("move 2 from 2 to 1\nmove 2 from 1 to 2\nmove 1 from 2 to 1\nmove 1 from 2 to 1\nmove 1 from 3 to 3", 1).unwrap();
+    let ragged_stacks = parse_initial_stacks(ragged).unwrap();
+    assert_eq!(vec![Move { from: 2, to: 1, count: 2, line: 3 }], optimize_moves(&CrateMover9000, &ragged_stacks, &redundant_moves));
+    assert_eq!(redundant_moves[2..4].to_vec(), optimize_moves(&CrateMover9001, &ragged_stacks, &redundant_moves));
+
     println!("Reading input file...");
     let input_string = read_input();
     println!("Read {} lines.", input_string.lines().count());
 
     let (stacks_input, moves_input) = parse_input_sections(&input_string);
-    let mut stacks = parse_initial_stacks(stacks_input);
-    let moves = parse_moves(moves_input);
+    let initial_stacks = parse_initial_stacks(stacks_input).unwrap_or_else(|error| panic!("Invalid input! {}", error));
+    let moves = parse_moves(moves_input, stacks_input.lines().count() + 2).unwrap_or_else(|error| panic!("Invalid input! {}", error));
+    assert_eq!(Ok(initial_stacks.clone()), parse_initial_stacks(&render_stacks(&initial_stacks)));
+
+    if validate {
+        for crate_mover in &crate_movers {
+            let invalid_moves = validate_moves(crate_mover.as_ref(), &initial_stacks, &moves, first_invalid_move_only);
+            println!("Found {} invalid moves for {}", invalid_moves.len(), crate_mover.name());
+            for invalid_move in invalid_moves {
+                println!("  {}", invalid_move);
+            }
+        }
+        return;
+    }
+
+    if metrics || optimize {
+        for crate_mover in &crate_movers {
+            println!("Crane metrics for {}:", crate_mover.name());
+            print_crane_metrics(&calc_crane_metrics(crate_mover.as_ref(), &initial_stacks, &moves));
+            if optimize {
+                let optimized_moves = optimize_moves(crate_mover.as_ref(), &initial_stacks, &moves);
+                println!("Optimized {} moves into {} moves:", moves.len(), optimized_moves.len());
+                for mov in &optimized_moves {
+                    println!("move {} from {} to {}", mov.count, mov.from, mov.to);
+                }
+                println!("Crane metrics for the optimized moves:");
+                print_crane_metrics(&calc_crane_metrics(crate_mover.as_ref(), &initial_stacks, &optimized_moves));
+            }
+        }
+        return;
+    }
+
+    if let Some(target) = plan_target {
+        for crate_mover in &crate_movers {
+            println!("Planning moves for {}...", crate_mover.name());
+            match plan_moves(crate_mover.as_ref(), &initial_stacks, &target, plan_max_depth, plan_max_states) {
+                Err(PlanError::UnsupportedModel) => println!("Cannot plan for {}, its moves depend on earlier lifts", crate_mover.name()),
+                Err(PlanError::Unreachable) => println!("Target cannot be reached from the initial stacks"),
+                Err(PlanError::LimitReached { depth, states }) => {
+                    println!("No plan found within {} moves after exploring {} states", depth, states)
+                }
+                Ok(planned_moves) => {
+                    println!("Found a plan with {} moves:", planned_moves.len());
+                    for mov in &planned_moves {
+                        println!("move {} from {} to {}", mov.count, mov.from, mov.to);
+                    }
+                    let mut stacks = initial_stacks.clone();
+                    rearrange(crate_mover.as_ref(), &mut stacks, &planned_moves).expect("Planned moves are valid");
+                    print_stack_tops(&stacks);
+                }
+            }
+        }
+        return;
+    }
 
-    println!("Rearrange crates with CrateMover 9000...");
-    rearrange_with_crate_mover_9000(&mut stacks, &moves);
-    print_stack_tops(&mut stacks);
+    if let Some(rebuild_path) = rebuild_path {
+        let final_drawing = std::fs::read_to_string(rebuild_path).unwrap_or_else(|error| panic!("Failed to read {} - error: {}", rebuild_path, error));
+        let final_stacks = parse_initial_stacks(&final_drawing).unwrap_or_else(|error| panic!("Invalid drawing in {}! {}", rebuild_path, error));
+        for crate_mover in &crate_movers {
+            println!("Initial stacks rebuilt by undoing all moves of {}:", crate_mover.name());
+            match rebuild_initial_stacks(crate_mover.as_ref(), &final_stacks, &moves) {
+                Err(error) => println!("Cannot undo {}", error),
+                Ok(stacks) => print!("{}", render_stacks(&stacks)),
+            }
+        }
+        return;
+    }
 
-    println!("Rearrange crates with CrateMover 9001...");
-    stacks = parse_initial_stacks(stacks_input);
-    rearrange_with_crate_mover_9001(&mut stacks, &moves);
-    print_stack_tops(&mut stacks);
+    if animate {
+        for crate_mover in &crate_movers {
+            let mut player = Player::new(crate_mover.as_ref(), &initial_stacks, &moves).unwrap_or_else(|error| panic!("Cannot animate {} - error: {}", crate_mover.name(), error));
+            match (frames_dir, speed) {
+                (Some(dir), _) => player.dump_frames(Path::new(dir)),
+                (None, Some(speed)) => player.play(speed),
+                (None, None) => player.step_interactively(),
+            }
+        }
+        return;
+    }
+
+    if let Some(step) = render_step {
+        let step = step.unwrap_or(moves.len()).min(moves.len());
+        for crate_mover in &crate_movers {
+            println!("Stacks after {} of {} moves with {}:", step, moves.len(), crate_mover.name());
+            let mut stacks = initial_stacks.clone();
+            match rearrange(crate_mover.as_ref(), &mut stacks, &moves[..step]) {
+                Err(error) => println!("{} failed to rearrange crates: {}", crate_mover.name(), error),
+                Ok(()) => print!("{}", render_stacks(&stacks)),
+            }
+        }
+        return;
+    }
+
+    for crate_mover in &crate_movers {
+        println!("Rearrange crates with {}...", crate_mover.name());
+        let mut stacks = initial_stacks.clone();
+        match rearrange(crate_mover.as_ref(), &mut stacks, &moves) {
+            Err(error) => println!("{} failed to rearrange crates: {}", crate_mover.name(), error),
+            Ok(()) => {
+                print_stack_tops(&stacks);
+                // every rearrangement must be reversible
+                assert_eq!(Ok(initial_stacks.clone()), rebuild_initial_stacks(crate_mover.as_ref(), &stacks, &moves));
+            }
+        }
+    }
 
     /********************************************
      * Reading input file...
@@ -45,105 +303,732 @@ fn main() {
      ********************************************/
 }
 
-fn rearrange_with_crate_mover_9000(stacks: &mut Vec<Vec<String>>, moves: &Vec<Move>) {
-    for (_i, mov) in moves.iter().enumerate() {
-        for _j in 1..mov.count + 1 {
-            let from_stack = &mut stacks[mov.from];
-            let c = &from_stack.pop().unwrap();
-            let _ = &stacks[mov.to].push(c.to_string());
+impl CrateMover for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn lifts(&self, mov: &Move, _first_lift_number: usize) -> Vec<Lift> {
+        vec![Lift { count: 1, reversed: false }; mov.count]
+    }
+}
+
+impl CrateMover for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_string()
+    }
+
+    fn lifts(&self, mov: &Move, _first_lift_number: usize) -> Vec<Lift> {
+        vec![Lift { count: mov.count, reversed: false }]
+    }
+}
+
+impl CrateMover for LimitedCrateMover {
+    fn name(&self) -> String {
+        format!("CrateMover limited to {} crates per lift", self.max_crates_per_lift)
+    }
+
+    fn lifts(&self, mov: &Move, _first_lift_number: usize) -> Vec<Lift> {
+        let mut lifts = vec![Lift { count: self.max_crates_per_lift, reversed: false }; mov.count / self.max_crates_per_lift];
+        if !mov.count.is_multiple_of(self.max_crates_per_lift) {
+            lifts.push(Lift { count: mov.count % self.max_crates_per_lift, reversed: false });
         }
+        lifts
+    }
+}
+
+impl CrateMover for AlternatingCrateMover {
+    fn name(&self) -> String {
+        "CrateMover reversing every other lift".to_string()
+    }
+
+    fn lifts(&self, mov: &Move, first_lift_number: usize) -> Vec<Lift> {
+        vec![Lift { count: mov.count, reversed: first_lift_number % 2 == 1 }]
+    }
+
+    fn is_stateless(&self) -> bool {
+        false
+    }
+}
+
+impl CrateMover for CapacityCrateMover {
+    fn name(&self) -> String {
+        format!("CrateMover with a capacity of {} crates per stack", self.capacity)
+    }
+
+    fn lifts(&self, mov: &Move, first_lift_number: usize) -> Vec<Lift> {
+        CrateMover9001.lifts(mov, first_lift_number)
+    }
+
+    fn stack_capacity(&self) -> Option<usize> {
+        Some(self.capacity)
     }
 }
 
-fn rearrange_with_crate_mover_9001(stacks: &mut Vec<Vec<String>>, moves: &Vec<Move>) {
-    for (_i, mov) in moves.iter().enumerate() {
-        let mut temp = vec![];
-        for _j in 1..mov.count + 1 {
-            let from_stack = &mut stacks[mov.from];
-            let c = &from_stack.pop().unwrap();
-            let _ = &temp.push(c.to_string());
+fn crate_mover_by_name(name: &str) -> Result<Box<dyn CrateMover>, String> {
+    let parse_limit = |limit: &str| match limit.parse::<usize>() {
+        Ok(limit) if limit > 0 => Ok(limit),
+        _ => Err(format!("Expected a positive number but got {:?}", limit)),
+    };
+    match name {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        "alternating" => Ok(Box::new(AlternatingCrateMover)),
+        _ => {
+            if let Some(limit) = name.strip_prefix("limited:") {
+                Ok(Box::new(LimitedCrateMover { max_crates_per_lift: parse_limit(limit)? }))
+            } else if let Some(limit) = name.strip_prefix("capacity:") {
+                Ok(Box::new(CapacityCrateMover { capacity: parse_limit(limit)? }))
+            } else {
+                Err("Unknown model".to_string())
+            }
+        }
+    }
+}
+
+// move execution shared by all crane models, stops at the first invalid move
+fn rearrange(crate_mover: &dyn CrateMover, stacks: &mut [Vec<String>], moves: &[Move]) -> Result<(), InvalidMove> {
+    rearrange_from_lift(crate_mover, stacks, moves, 0).map(|_lift_count| ())
+}
+
+// continues a rearrangement after the given number of lifts, returns the number of lifts afterwards
+fn rearrange_from_lift(crate_mover: &dyn CrateMover, stacks: &mut [Vec<String>], moves: &[Move], first_lift_number: usize) -> Result<usize, InvalidMove> {
+    let mut lift_number = first_lift_number;
+    for mov in moves.iter() {
+        let stack_sizes: Vec<usize> = stacks.iter().map(|stack| stack.len()).collect();
+        if let Some(violation) = check_move(mov, &stack_sizes, crate_mover.stack_capacity()) {
+            return Err(InvalidMove { mov: mov.clone(), stack_sizes: stack_sizes[1..].to_vec(), violation });
         }
-        // push temp slice to restore correct order
-        for _j in 0..temp.len() {
-            let c = &temp.pop().unwrap();
-            let _ = &stacks[mov.to].push(c.to_string());
+        let lifts = crate_mover.lifts(mov, lift_number);
+        lift_number += lifts.len();
+        for lift in lifts {
+            execute_lift(stacks, mov, &lift);
         }
     }
+    Ok(lift_number)
+}
+
+fn execute_lift<T>(stacks: &mut [Vec<T>], mov: &Move, lift: &Lift) {
+    let from_stack = &mut stacks[mov.from];
+    let mut block = from_stack.split_off(from_stack.len() - lift.count);
+    if lift.reversed {
+        block.reverse();
+    }
+    stacks[mov.to].extend(block);
+}
+
+// undoes a move by lifting the same blocks back in reverse order
+fn undo_move(crate_mover: &dyn CrateMover, stacks: &mut [Vec<String>], mov: &Move, first_lift_number: usize) -> Result<(), InvalidMove> {
+    let inverted = Move { from: mov.to, to: mov.from, ..mov.clone() };
+    let stack_sizes: Vec<usize> = stacks.iter().map(|stack| stack.len()).collect();
+    if let Some(violation) = check_move(&inverted, &stack_sizes, None) {
+        return Err(InvalidMove { mov: inverted, stack_sizes: stack_sizes[1..].to_vec(), violation });
+    }
+    for lift in crate_mover.lifts(mov, first_lift_number).iter().rev() {
+        execute_lift(stacks, &inverted, lift);
+    }
+    Ok(())
 }
 
-fn print_stack_tops(stacks: &mut Vec<Vec<String>>) {
-    // beware that this is mutating the stacks. only print at the end of process
-    for i in 1..stacks.len() {
-        print!("{}", stacks[i].pop().unwrap().as_str());
+// undoes all moves on a final state, e.g. to check that a generated puzzle is consistent
+fn rebuild_initial_stacks(crate_mover: &dyn CrateMover, final_stacks: &[Vec<String>], moves: &[Move]) -> Result<Vec<Vec<String>>, InvalidMove> {
+    let mut first_lift_numbers = Vec::new();
+    let mut lift_number = 0;
+    for mov in moves.iter() {
+        first_lift_numbers.push(lift_number);
+        lift_number += crate_mover.lifts(mov, lift_number).len();
+    }
+    let mut stacks = final_stacks.to_vec();
+    for (mov, first_lift_number) in moves.iter().zip(first_lift_numbers).rev() {
+        undo_move(crate_mover, &mut stacks, mov, first_lift_number)?;
+    }
+    Ok(stacks)
+}
+
+impl<'a> History<'a> {
+    fn new(crate_mover: &'a dyn CrateMover, stacks: &[Vec<String>]) -> History<'a> {
+        History { crate_mover, stacks: stacks.to_vec(), applied: Vec::new(), undone: Vec::new(), lift_number: 0 }
+    }
+
+    // applying a new move discards all undone moves
+    fn apply(&mut self, mov: Move) -> Result<(), InvalidMove> {
+        self.execute(mov)?;
+        self.undone.clear();
+        Ok(())
+    }
+
+    fn execute(&mut self, mov: Move) -> Result<(), InvalidMove> {
+        let first_lift_number = self.lift_number;
+        self.lift_number = rearrange_from_lift(self.crate_mover, &mut self.stacks, std::slice::from_ref(&mov), first_lift_number)?;
+        self.applied.push((mov, first_lift_number));
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Option<Move> {
+        let (mov, first_lift_number) = self.applied.pop()?;
+        undo_move(self.crate_mover, &mut self.stacks, &mov, first_lift_number).expect("Applied moves can always be undone");
+        self.lift_number = first_lift_number;
+        self.undone.push(mov.clone());
+        Some(mov)
+    }
+
+    fn redo(&mut self) -> Option<Move> {
+        let mov = self.undone.pop()?;
+        self.execute(mov.clone()).expect("Undone moves can always be redone");
+        Some(mov)
+    }
+}
+
+// tops are given as one character per stack, or comma separated for longer labels
+fn parse_plan_tops(tops: &str) -> PlanTarget {
+    let labels: Vec<String> = if tops.contains(',') {
+        tops.split(',').map(|label| label.trim().to_string()).collect()
+    } else {
+        tops.chars().map(|label| label.to_string()).collect()
+    };
+    PlanTarget::Tops(labels.into_iter().map(|label| if label == "?" || label.is_empty() { None } else { Some(label) }).collect())
+}
+
+// best-first search over hashed states, preferring few moves and states close to the target
+fn plan_moves(crate_mover: &dyn CrateMover, initial_stacks: &[Vec<String>], target: &PlanTarget, max_depth: usize, max_states: usize) -> Result<Vec<Move>, PlanError> {
+    if !crate_mover.is_stateless() {
+        return Err(PlanError::UnsupportedModel);
+    }
+    // intern labels, so that states are cheap to clone and hash
+    let mut label_ids: HashMap<String, u16> = HashMap::new();
+    for label in initial_stacks.iter().flatten() {
+        let next_id = label_ids.len() as u16;
+        label_ids.entry(label.clone()).or_insert(next_id);
+    }
+    let initial: PlanState = initial_stacks.iter().map(|stack| stack.iter().map(|label| label_ids[label]).collect()).collect();
+    // distance estimates how far a state is from the target, 0 means it is reached
+    let distance: Box<dyn Fn(&PlanState) -> usize> = match target {
+        PlanTarget::Tops(tops) => {
+            if tops.len() > initial.len() - 1 {
+                return Err(PlanError::Unreachable);
+            }
+            let mut required: Vec<Option<u16>> = Vec::new();
+            for top in tops {
+                match top {
+                    Some(label) => match label_ids.get(label) {
+                        Some(id) => required.push(Some(*id)),
+                        None => return Err(PlanError::Unreachable),
+                    },
+                    None => required.push(None),
+                }
+            }
+            // every required top needs a crate of its own
+            for id in required.iter().flatten() {
+                let needed = required.iter().filter(|other| **other == Some(*id)).count();
+                let available = initial.iter().flatten().filter(|crate_id| *crate_id == id).count();
+                if needed > available {
+                    return Err(PlanError::Unreachable);
+                }
+            }
+            // stacks showing the wrong top
+            Box::new(move |state: &PlanState| required.iter().enumerate().filter(|(i, top)| top.is_some() && state[i + 1].last() != top.as_ref()).count())
+        }
+        PlanTarget::Arrangement(stacks) => {
+            let mut wanted: PlanState = Vec::new();
+            for stack in stacks {
+                match stack.iter().map(|label| label_ids.get(label).copied()).collect::<Option<Vec<u16>>>() {
+                    Some(ids) => wanted.push(ids),
+                    None => return Err(PlanError::Unreachable),
+                }
+            }
+            let mut wanted_crates: Vec<u16> = wanted.iter().flatten().copied().collect();
+            let mut initial_crates: Vec<u16> = initial.iter().flatten().copied().collect();
+            wanted_crates.sort();
+            initial_crates.sort();
+            if wanted.len() != initial.len() || wanted_crates != initial_crates {
+                return Err(PlanError::Unreachable);
+            }
+            // crates sitting above the part of their stack that already matches the target
+            Box::new(move |state: &PlanState| {
+                state
+                    .iter()
+                    .zip(wanted.iter())
+                    .map(|(stack, wanted_stack)| stack.len() - stack.iter().zip(wanted_stack.iter()).take_while(|(a, b)| a == b).count())
+                    .sum()
+            })
+        }
+    };
+
+    // states[i] was reached from states[parent] with the move
+    let mut states: Vec<PlanState> = vec![initial.clone()];
+    let mut parents: Vec<Option<(usize, Move)>> = vec![None];
+    let mut depths: Vec<usize> = vec![0];
+    let mut seen: HashMap<PlanState, usize> = HashMap::from([(initial, 0)]);
+    // ordered by moves so far plus weighted distance, ties go to the state found first
+    let mut queue: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::from([Reverse((PLAN_DISTANCE_WEIGHT * distance(&states[0]), 0))]);
+    let mut limit_reached = false;
+    while let Some(Reverse((_priority, index))) = queue.pop() {
+        if distance(&states[index]) == 0 {
+            let mut planned_moves = Vec::new();
+            let mut current = index;
+            while let Some((parent, mov)) = &parents[current] {
+                planned_moves.push(mov.clone());
+                current = *parent;
+            }
+            planned_moves.reverse();
+            return Ok(planned_moves);
+        }
+        if depths[index] >= max_depth {
+            limit_reached = true;
+            continue;
+        }
+        let stack_sizes: Vec<usize> = states[index].iter().map(|stack| stack.len()).collect();
+        for from in 1..stack_sizes.len() {
+            for to in (1..stack_sizes.len()).filter(|to| *to != from) {
+                for count in 1..=stack_sizes[from] {
+                    let mov = Move { from, to, count, line: 0 };
+                    if check_move(&mov, &stack_sizes, crate_mover.stack_capacity()).is_some() {
+                        continue;
+                    }
+                    let mut next = states[index].clone();
+                    for lift in crate_mover.lifts(&mov, 0) {
+                        execute_lift(&mut next, &mov, &lift);
+                    }
+                    if seen.contains_key(&next) {
+                        continue;
+                    }
+                    if states.len() >= max_states {
+                        return Err(PlanError::LimitReached { depth: depths[index], states: states.len() });
+                    }
+                    seen.insert(next.clone(), states.len());
+                    queue.push(Reverse((depths[index] + 1 + PLAN_DISTANCE_WEIGHT * distance(&next), states.len())));
+                    states.push(next);
+                    parents.push(Some((index, mov)));
+                    depths.push(depths[index] + 1);
+                }
+            }
+        }
+    }
+    if limit_reached {
+        return Err(PlanError::LimitReached { depth: max_depth, states: states.len() });
+    }
+    Err(PlanError::Unreachable)
+}
+
+// metrics of a valid move list, moves past the first invalid one are not counted
+fn calc_crane_metrics(crate_mover: &dyn CrateMover, initial_stacks: &[Vec<String>], moves: &[Move]) -> CraneMetrics {
+    let mut stack_sizes: Vec<usize> = initial_stacks.iter().map(|stack| stack.len()).collect();
+    let mut metrics = CraneMetrics { max_stack_height: stack_sizes.iter().copied().max().unwrap_or(0), ..Default::default() };
+    let mut crane_position: Option<usize> = None;
+    let mut lift_number = 0;
+    for mov in moves.iter() {
+        if check_move(mov, &stack_sizes, crate_mover.stack_capacity()).is_some() {
+            break;
+        }
+        for lift in crate_mover.lifts(mov, lift_number) {
+            metrics.distance += crane_position.unwrap_or(mov.from).abs_diff(mov.from) + mov.from.abs_diff(mov.to);
+            crane_position = Some(mov.to);
+            metrics.crates_lifted += lift.count;
+            metrics.lifts += 1;
+            stack_sizes[mov.from] -= lift.count;
+            stack_sizes[mov.to] += lift.count;
+            metrics.max_stack_height = metrics.max_stack_height.max(stack_sizes[mov.to]);
+            lift_number += 1;
+        }
+    }
+    metrics
+}
+
+fn print_crane_metrics(metrics: &CraneMetrics) {
+    println!("  Total crates lifted:\t{}", metrics.crates_lifted);
+    println!("  Lifts:\t\t{}", metrics.lifts);
+    println!("  Max stack height:\t{}", metrics.max_stack_height);
+    println!("  Distance travelled:\t{}", metrics.distance);
+}
+
+// candidates replacing two consecutive moves, only used if a simulation confirms the same result
+fn combine_moves(first: &Move, second: &Move) -> Vec<Vec<Move>> {
+    let mut candidates = vec![vec![]]; // both cancel out
+    if first.from == second.from && first.to == second.to {
+        candidates.push(vec![Move { count: first.count + second.count, ..first.clone() }]);
+    }
+    if first.to == second.from && first.count == second.count && first.from != second.to {
+        candidates.push(vec![Move { to: second.to, ..first.clone() }]);
+    }
+    candidates
+}
+
+// drops moves without effect and merges or cancels consecutive moves, keeping the final state
+fn optimize_moves(crate_mover: &dyn CrateMover, initial_stacks: &[Vec<String>], moves: &[Move]) -> Vec<Move> {
+    if !crate_mover.is_stateless() || !validate_moves(crate_mover, initial_stacks, moves, true).is_empty() {
+        return moves.to_vec();
+    }
+    let simulate = |stacks: &[Vec<String>], moves: &[Move]| {
+        let mut stacks = stacks.to_vec();
+        rearrange(crate_mover, &mut stacks, moves).ok().map(|()| stacks)
+    };
+    let mut optimized: Vec<Move> = Vec::new();
+    // states[i] holds the stacks before optimized[i], the last one the current stacks
+    let mut states: Vec<Vec<Vec<String>>> = vec![initial_stacks.to_vec()];
+    for mov in moves.iter() {
+        let after = simulate(states.last().unwrap(), std::slice::from_ref(mov)).expect("Moves were validated");
+        if after == *states.last().unwrap() {
+            continue; // e.g. a move onto the same stack
+        }
+        let mut pending = vec![mov.clone()];
+        // keep combining with the previous move as long as that yields the same stacks
+        while let (Some(previous), [current]) = (optimized.last(), pending.as_slice()) {
+            let before_previous = &states[states.len() - 2];
+            let replacement = combine_moves(previous, current).into_iter().find(|candidate| simulate(before_previous, candidate).as_ref() == Some(&after));
+            match replacement {
+                Some(replacement) => {
+                    optimized.pop();
+                    states.pop();
+                    pending = replacement;
+                }
+                None => break,
+            }
+        }
+        optimized.extend(pending);
+        states.truncate(optimized.len());
+        states.push(after);
+    }
+    // the optimized moves must lead to the very same stacks
+    if simulate(initial_stacks, &optimized) != simulate(initial_stacks, moves) {
+        return moves.to_vec();
+    }
+    optimized
+}
+
+// stack_sizes[0] belongs to the unused stack 0, like the stacks themselves
+fn check_move(mov: &Move, stack_sizes: &[usize], stack_capacity: Option<usize>) -> Option<MoveViolation> {
+    if mov.count == 0 {
+        return Some(MoveViolation::ZeroCount);
+    }
+    for stack in [mov.from, mov.to] {
+        if stack == 0 || stack >= stack_sizes.len() {
+            return Some(MoveViolation::UnknownStack(stack));
+        }
+    }
+    if stack_sizes[mov.from] < mov.count {
+        return Some(MoveViolation::Underflow);
+    }
+    if let Some(capacity) = stack_capacity {
+        if mov.from != mov.to && stack_sizes[mov.to] + mov.count > capacity {
+            return Some(MoveViolation::CapacityExceeded(capacity));
+        }
+    }
+    None
+}
+
+// dry run on stack sizes only, invalid moves are reported and skipped
+fn validate_moves(crate_mover: &dyn CrateMover, stacks: &[Vec<String>], moves: &[Move], stop_at_first: bool) -> Vec<InvalidMove> {
+    let mut stack_sizes: Vec<usize> = stacks.iter().map(|stack| stack.len()).collect();
+    let mut invalid_moves = Vec::new();
+    for mov in moves.iter() {
+        match check_move(mov, &stack_sizes, crate_mover.stack_capacity()) {
+            Some(violation) => {
+                invalid_moves.push(InvalidMove { mov: mov.clone(), stack_sizes: stack_sizes[1..].to_vec(), violation });
+                if stop_at_first {
+                    break;
+                }
+            }
+            None => {
+                stack_sizes[mov.from] -= mov.count;
+                stack_sizes[mov.to] += mov.count;
+            }
+        }
+    }
+    invalid_moves
+}
+
+impl std::fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let reason = match self.violation {
+            MoveViolation::ZeroCount => "moves zero crates".to_string(),
+            MoveViolation::UnknownStack(stack) => format!("stack {} does not exist", stack),
+            MoveViolation::Underflow => format!("stack {} holds only {} crates", self.mov.from, self.stack_sizes[self.mov.from - 1]),
+            MoveViolation::CapacityExceeded(capacity) => format!("stack {} would exceed its capacity of {} crates", self.mov.to, capacity),
+        };
+        let stack_sizes: Vec<String> = self.stack_sizes.iter().enumerate().map(|(i, size)| format!("{}:{}", i + 1, size)).collect();
+        write!(
+            f,
+            "Line {}: move {} from {} to {} is invalid, {} (stack sizes {})",
+            self.mov.line, self.mov.count, self.mov.from, self.mov.to, reason, stack_sizes.join(" ")
+        )
+    }
+}
+
+fn print_stack_tops(stacks: &[Vec<String>]) {
+    for stack in stacks.iter().skip(1) {
+        match stack.last() {
+            Some(top) => print!("[{}]", top),
+            None => print!("   "),
+        }
     }
     println!();
 }
 
-fn parse_moves(moves_input: &str) -> Vec<Move> {
+// draws the stacks in the puzzle's input format, so that parse_initial_stacks can read them back
+fn render_stacks(stacks: &[Vec<String>]) -> String {
+    render_stacks_highlighted(stacks, &[], false)
+}
+
+// highlighted crates are given as (stack, level from the bottom) and drawn in color,
+// or as <X> instead of [X] without ansi colors
+fn render_stacks_highlighted(stacks: &[Vec<String>], highlighted: &[(usize, usize)], ansi: bool) -> String {
+    let stack_count = stacks.len() - 1;
+    let label_width = stacks.iter().flatten().map(|label| label.chars().count()).max().unwrap_or(1);
+    // all cells are as wide as the widest crate or stack number
+    let cell_width = (label_width + 2).max(stack_count.to_string().len());
+    let cell_center = (cell_width - 1) / 2;
+    // tracks the visible width separately, as ansi escape codes take no space on screen
+    let centered = |line: &mut String, line_width: &mut usize, column: usize, token: &str, color: Option<&str>| {
+        let token_width = token.chars().count();
+        let start = column * (cell_width + 1) + cell_center - (token_width - 1) / 2;
+        line.push_str(&" ".repeat(start - *line_width));
+        match color {
+            Some(color) => line.push_str(&format!("{}{}{}", color, token, ANSI_RESET)),
+            None => line.push_str(token),
+        }
+        *line_width = start + token_width;
+    };
+    let mut drawing = String::new();
+    let height = stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    for level in (0..height).rev() {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for (column, stack) in stacks[1..].iter().enumerate() {
+            if let Some(label) = stack.get(level) {
+                let is_highlighted = highlighted.contains(&(column + 1, level));
+                match (is_highlighted, ansi) {
+                    (true, true) => centered(&mut line, &mut line_width, column, &format!("[{}]", label), Some(ANSI_HIGHLIGHT)),
+                    (true, false) => centered(&mut line, &mut line_width, column, &format!("<{}>", label), None),
+                    (false, _) => centered(&mut line, &mut line_width, column, &format!("[{}]", label), None),
+                }
+            }
+        }
+        drawing.push_str(&line);
+        drawing.push('\n');
+    }
+    let mut footer = String::new();
+    let mut footer_width = 0;
+    for column in 0..stack_count {
+        centered(&mut footer, &mut footer_width, column, &(column + 1).to_string(), None);
+    }
+    // like the puzzle input, the footer spans the full width of the last cell
+    let footer_padding = (stack_count * (cell_width + 1)).saturating_sub(1 + footer_width);
+    drawing.push_str(&footer);
+    drawing.push_str(&" ".repeat(footer_padding));
+    drawing.push('\n');
+    drawing
+}
+
+impl<'a> Player<'a> {
+    fn new(crate_mover: &'a dyn CrateMover, initial_stacks: &[Vec<String>], moves: &'a [Move]) -> Result<Player<'a>, InvalidMove> {
+        if let Some(invalid_move) = validate_moves(crate_mover, initial_stacks, moves, true).pop() {
+            return Err(invalid_move);
+        }
+        Ok(Player { history: History::new(crate_mover, initial_stacks), moves })
+    }
+
+    fn step(&self) -> usize {
+        self.history.applied.len()
+    }
+
+    // steps forth by redoing or applying the next move, and back by undoing
+    fn jump_to(&mut self, step: isize) {
+        let target = step.clamp(0, self.moves.len() as isize) as usize;
+        while self.step() > target {
+            self.history.undo();
+        }
+        while self.step() < target {
+            if self.history.redo().is_none() {
+                let mov = self.moves[self.step()].clone();
+                self.history.apply(mov).expect("Moves were validated");
+            }
+        }
+    }
+
+    // the crates moved by the latest move end up on top of its target stack
+    fn render_frame(&self, ansi: bool) -> String {
+        let stacks = &self.history.stacks;
+        let (title, highlighted) = match self.step() {
+            0 => ("initial stacks".to_string(), vec![]),
+            step => {
+                let mov = &self.moves[step - 1];
+                let to_height = stacks[mov.to].len();
+                let highlighted: Vec<(usize, usize)> = (to_height - mov.count..to_height).map(|level| (mov.to, level)).collect();
+                (format!("move {} from {} to {} (line {})", mov.count, mov.from, mov.to, mov.line), highlighted)
+            }
+        };
+        format!(
+            "Step {} of {} with {}: {}\n{}",
+            self.step(),
+            self.moves.len(),
+            self.history.crate_mover.name(),
+            title,
+            render_stacks_highlighted(stacks, &highlighted, ansi)
+        )
+    }
+
+    fn redraw(&self) {
+        print!("{}{}", ANSI_CLEAR_SCREEN, self.render_frame(true));
+        io::stdout().flush().unwrap();
+    }
+
+    fn play(&mut self, speed: Duration) {
+        for step in 0..=self.moves.len() {
+            self.jump_to(step as isize);
+            self.redraw();
+            thread::sleep(speed);
+        }
+    }
+
+    fn step_interactively(&mut self) {
+        self.redraw();
+        let stdin = io::stdin();
+        loop {
+            print!("[enter] next, p previous, +n/-n skip, <step> jump, q quit > ");
+            io::stdout().flush().unwrap();
+            let mut command = String::new();
+            if stdin.lock().read_line(&mut command).unwrap_or(0) == 0 {
+                break; // end of input
+            }
+            let step = self.step() as isize;
+            match command.trim() {
+                "" | "n" => self.jump_to(step + 1),
+                "p" => self.jump_to(step - 1),
+                "q" => break,
+                command => match command.parse::<isize>() {
+                    Ok(offset) if command.starts_with(['+', '-']) => self.jump_to(step + offset),
+                    Ok(target) => self.jump_to(target),
+                    Err(_) => {
+                        println!("Unknown command {:?}", command);
+                        continue;
+                    }
+                },
+            }
+            self.redraw();
+        }
+    }
+
+    fn dump_frames(&mut self, dir: &Path) {
+        if let Err(error) = std::fs::create_dir_all(dir) {
+            panic!("Failed to create {} - error: {}", dir.display(), error);
+        }
+        let model = self.history.crate_mover.name().to_lowercase().replace(' ', "_");
+        for step in 0..=self.moves.len() {
+            self.jump_to(step as isize);
+            let frame_path = dir.join(format!("{}_{:05}.txt", model, step));
+            if let Err(error) = std::fs::write(&frame_path, self.render_frame(false)) {
+                panic!("Failed to write {} - error: {}", frame_path.display(), error);
+            }
+        }
+        println!("Successfully wrote {} frames for {} to {}", self.moves.len() + 1, self.history.crate_mover.name(), dir.display());
+    }
+}
+
+// first_line is the line number of the first move within the input file
+fn parse_moves(moves_input: &str, first_line: usize) -> Result<Vec<Move>, String> {
     let mut moves = Vec::new();
-    for line in moves_input.lines() {
-        let move_data: Vec<usize> = line.split(" ").flat_map(|a| a.parse::<usize>()).collect();
-        moves.push(Move { from: move_data[1], to: move_data[2], count: move_data[0] });
+    for (line_index, line) in moves_input.lines().enumerate().filter(|(_i, line)| !line.trim().is_empty()) {
+        // line:            "move 1 from 8 to 4"
+        let move_data: Vec<usize> = match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["move", count, "from", from, "to", to] => [count, from, to].iter().flat_map(|a| a.parse::<usize>()).collect(),
+            _ => vec![],
+        };
+        if move_data.len() != 3 {
+            return Err(format!("Line {}: expected a move like \"move 1 from 2 to 3\" but found {:?}", first_line + line_index, line));
+        }
+        moves.push(Move { from: move_data[1], to: move_data[2], count: move_data[0], line: first_line + line_index });
     }
     if _DEBUG {
         println!("{:#?}", moves);
     }
-    moves
+    Ok(moves)
 }
 
-fn parse_input_sections(input_string: &String) -> (&str, &str) {
+fn parse_input_sections(input_string: &str) -> (&str, &str) {
     let sections: Vec<&str> = input_string.split("\n\n").collect();
     (sections[0], sections[1])
 }
 
-fn parse_initial_stacks(stacks_string: &str) -> Vec<Vec<String>> {
-    let mut stacks: Vec<Vec<String>> = vec![vec![]];
-    let mut stacks_horizontal: Vec<Vec<String>> = Vec::new();
-    let stack_line_pattern = Regex::new(r"(\[[A-Z]{1}\])*").unwrap();
-    for line in stacks_string.lines() {
-        if !line.starts_with("[") {
-            break; // we reached end of stacks block
-        }
-        // line:            "[V]-----[B]---------------------[F]"
-        let trimmed_line = line.replace("    ", " "); // reduce 4 spaces to 1 for regex to work
-        let a = trimmed_line.as_str();
-        // trimmed_line:    "[V]--[B]------[F]"
-        let row = stack_line_pattern
-            .find_iter(a)
-            .map(|a| a.as_str().to_string())
-            .collect::<Vec<String>>();
-        // row:             ["[V]", "", "[B]", "", "", "", "", "", "[F]"]
-        stacks_horizontal.push(row);
-    }
-    // transform into vertical stacks...
-    for (_i, row) in stacks_horizontal.iter().enumerate() {
-        for (j, cell) in row.iter().enumerate() {
-            if stacks.get(j + 1).is_some() {
-                if !cell.is_empty() {
-                    stacks[j + 1].push(cell.clone());
-                }
-            } else {
-                if !cell.is_empty() {
-                    stacks.push(vec![cell.clone()]);
-                } else {
-                    stacks.push(vec![]);
-                }
-            }
+// the numbered footer line defines the stacks, every crate belongs to the stack whose number it is drawn above
+fn parse_initial_stacks(stacks_string: &str) -> Result<Vec<Vec<String>>, String> {
+    let lines: Vec<&str> = stacks_string.lines().map(|line| line.trim_end()).collect();
+    let footer_index = match lines.iter().rposition(|line| !line.is_empty()) {
+        Some(index) => index,
+        None => return Err("Drawing is empty, expected crates and a numbered footer line".to_string()),
+    };
+
+    // footer:          " 1   2   3 "
+    let mut stack_columns: Vec<usize> = Vec::new();
+    for (number, (start, label)) in tokenize_drawing_line(lines[footer_index]).into_iter().enumerate() {
+        if label.parse::<usize>() != Ok(number + 1) {
+            return Err(format!("Line {}: expected stack number {} in footer but found {:?}", footer_index + 1, number + 1, label));
         }
+        // a stack sits at the center of its number
+        stack_columns.push(start + (label.chars().count() - 1) / 2);
+    }
+    if stack_columns.is_empty() {
+        return Err(format!("Line {}: footer does not number any stacks", footer_index + 1));
     }
-    // reverse stacks in correct order (bottom-up)
-    for i in 0..stacks.len() {
-        stacks[i].reverse();
+
+    // rows top-down:   [[Some("V"), None, Some("B")], ...]
+    let mut rows: Vec<Vec<Option<String>>> = Vec::new();
+    for (line_index, line) in lines[..footer_index].iter().enumerate() {
+        let mut row: Vec<Option<String>> = vec![None; stack_columns.len()];
+        for (start, token) in tokenize_drawing_line(line) {
+            let end = start + token.chars().count() - 1;
+            let label = match token.strip_prefix('[').and_then(|token| token.strip_suffix(']')) {
+                Some(label) if !label.is_empty() && !label.contains(['[', ']']) => label,
+                _ => return Err(format!("Line {}: expected a crate like [A] at column {} but found {:?}", line_index + 1, start + 1, token)),
+            };
+            let stacks_below: Vec<usize> = (0..stack_columns.len()).filter(|i| start <= stack_columns[*i] && stack_columns[*i] <= end).collect();
+            let stack = match stacks_below.as_slice() {
+                [stack] => *stack,
+                [] => return Err(format!("Line {}: crate {} at column {} is not drawn above any stack number", line_index + 1, token, start + 1)),
+                _ => return Err(format!("Line {}: crate {} at column {} spans several stacks", line_index + 1, token, start + 1)),
+            };
+            row[stack] = Some(label.to_string());
+        }
+        rows.push(row);
+    }
+
+    // transform into vertical stacks bottom-up...
+    let mut stacks: Vec<Vec<String>> = vec![vec![]; stack_columns.len() + 1];
+    for (row_index, row) in rows.iter().enumerate().rev() {
+        for (i, cell) in row.iter().enumerate() {
+            match cell {
+                Some(label) if stacks[i + 1].len() == rows.len() - 1 - row_index => stacks[i + 1].push(label.clone()),
+                Some(label) => return Err(format!("Line {}: crate [{}] of stack {} is floating above an empty slot", row_index + 1, label, i + 1)),
+                None => (),
+            }
+        }
     }
     if _DEBUG {
         println!("{:#?}", stacks);
     }
-    stacks
+    Ok(stacks)
+}
+
+// whitespace separated tokens of a drawing line with their starting column
+fn tokenize_drawing_line(line: &str) -> Vec<(usize, String)> {
+    let mut tokens: Vec<(usize, String)> = Vec::new();
+    let mut previous_is_space = true;
+    for (column, c) in line.chars().enumerate() {
+        if c.is_whitespace() {
+            previous_is_space = true;
+            continue;
+        }
+        if previous_is_space {
+            tokens.push((column, String::new()));
+        }
+        tokens.last_mut().unwrap().1.push(c);
+        previous_is_space = false;
+    }
+    tokens
 }
 
 fn read_input() -> String {
     let input_path = Path::new("./src/input.txt");
-    let mut input_file = match File::open(&input_path) {
+    let mut input_file = match File::open(input_path) {
         Err(error) => panic!("Failed to open {} - error: {}", input_path.display(), error),
         Ok(file) => file,
     };
@@ -3,26 +3,170 @@
  * https://adventofcode.com/2022/day/6
  ********************************************/
 
-use std::{fs::File, io::Read, path::Path, collections::HashSet};
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
 
 const _DEBUG: bool = false;
+const START_OF_PACKET_WINDOW: usize = 4;
+const START_OF_MESSAGE_WINDOW: usize = 14;
+const SIMULATED_ROUND_TRIPS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MarkerEvent {
+    window: usize,
+    char_offset: usize, // characters read once the marker is complete
+    byte_offset: usize, // bytes read once the marker is complete
+}
+
+// sliding count of each character within the window, plus how many of them appear more than once
+#[derive(Debug)]
+struct MarkerDetector {
+    window: usize,
+    recent: VecDeque<char>,
+    counts: HashMap<char, usize>,
+    duplicates: usize,
+    chars_read: usize,
+    bytes_read: usize,
+}
+
+// half-open range of character indexes [start, end)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug)]
+struct Segmentation {
+    window: usize,
+    length: usize,
+    markers: Vec<usize>,   // character offsets right after every marker
+    preamble: Span,        // everything before the first marker is complete
+    frames: Vec<Span>,     // from the end of one marker up to the end of the next one
+    longest_gap: Span,     // longest run of characters none of which completes a marker
+}
+
+// frames as sent by the encoder, with the character offsets right after each inserted marker
+#[derive(Debug)]
+struct EncodedSignal {
+    signal: String,
+    markers: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+struct DecodedFrame {
+    marker: usize,
+    payload: Result<String, String>,
+}
+
+#[derive(Debug)]
+struct Xorshift {
+    state: u64,
+}
 
 fn main() {
+    // usage: day6 [--stream [--window=<size> ...]] to detect markers in a signal read from stdin
+    //        day6 --analyze [--input=<file>] [--window=<size> ...] [--json=<file>] to segment a whole signal
+    //        day6 --simulate [--window=<size>] [--frames=<count>] [--noise=<rate>] [--seed=<seed>] to send frames over a noisy channel
+    let args: Vec<String> = env::args().skip(1).collect();
+    let windows = parse_windows(&args);
+    if args.iter().any(|arg| arg == "--simulate") {
+        let option = |name: &str, default: &str| args.iter().find_map(|arg| arg.strip_prefix(name)).unwrap_or(default).to_string();
+        let frame_count = option("--frames=", "10").parse::<usize>().unwrap_or_else(|error| panic!("Invalid frame count - error: {}", error));
+        let noise = option("--noise=", "0.01").parse::<f64>().unwrap_or_else(|error| panic!("Invalid noise rate - error: {}", error));
+        let seed = option("--seed=", "2022").parse::<u64>().unwrap_or_else(|error| panic!("Invalid seed - error: {}", error));
+        check_frame_window(windows[0]).unwrap_or_else(|error| panic!("Invalid window - error: {}", error));
+        check_round_trips(SIMULATED_ROUND_TRIPS, seed);
+        simulate_channel(windows[0], frame_count, noise, seed);
+        return;
+    }
+    if args.iter().any(|arg| arg == "--analyze") {
+        let input_path = args.iter().find_map(|arg| arg.strip_prefix("--input=")).unwrap_or("./src/input.txt");
+        let input_string = fs::read_to_string(input_path).unwrap_or_else(|error| panic!("Failed to read {} - error: {}", input_path, error));
+        let segmentations: Vec<Segmentation> = windows.iter().filter_map(|window| segment_signal(&input_string, *window)).collect();
+        for segmentation in &segmentations {
+            print_segmentation(segmentation);
+        }
+        if let Some(json_path) = args.iter().find_map(|arg| arg.strip_prefix("--json=")) {
+            match fs::write(json_path, segmentations_to_json(&segmentations)) {
+                Err(error) => panic!("Failed to write {} - error: {}", json_path, error),
+                Ok(()) => println!("Segmentation written to {}", json_path),
+            }
+        }
+        return;
+    }
+    if args.iter().any(|arg| arg == "--stream") {
+        println!("Listening for markers on stdin...");
+        let events = decode_stream(io::stdin().lock(), &windows, print_marker_event).unwrap_or_else(|error| panic!("Failed to read signal - error: {}", error));
+        if events.len() < windows.len() {
+            println!("Signal ended after finding {} of {} markers", events.len(), windows.len());
+        }
+        return;
+    }
+
     println!("Reading input file...");
     let input_string = read_input();
 
-    assert_eq!(5, find_start_of_packet(&String::from("bvwbjplbgvbhsrlpgdmjqwftvncz")));
-    assert_eq!(6, find_start_of_packet(&String::from("nppdvjthqldpwncqszvftbrmjlhg")));
-    assert_eq!(10, find_start_of_packet(&String::from("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg")));
-    assert_eq!(11, find_start_of_packet(&String::from("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw")));
-    println!("End of first start-of-packet marker at index:\t {}", find_start_of_packet(&input_string));
+    assert_eq!(Some(7), find_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb", START_OF_PACKET_WINDOW));
+    assert_eq!(Some(1), find_marker("a", 1));
+    assert_eq!(Some(26), find_marker("abcdefghijklmnopqrstuvwxyz", 26));
+    assert_eq!(Some(27), find_marker("äabcdefghijklmnopqrstuvwxyz", 27));
+    assert_eq!(None, find_marker("abcabc", 4));
+    assert_eq!(None, find_marker("abc", 0));
+    assert_eq!(Some(5), find_start_of_packet("bvwbjplbgvbhsrlpgdmjqwftvncz"));
+    assert_eq!(Some(6), find_start_of_packet("nppdvjthqldpwncqszvftbrmjlhg"));
+    assert_eq!(Some(10), find_start_of_packet("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
+    assert_eq!(Some(11), find_start_of_packet("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
+    match find_start_of_packet(&input_string) {
+        Some(index) => println!("End of first start-of-packet marker at index:\t {}", index),
+        None => println!("No start-of-packet marker found"),
+    }
+
+    assert_eq!(Some(19), find_start_of_message("mjqjpqmgbljsphdztnvjfqwrcgsmlb"));
+    assert_eq!(Some(23), find_start_of_message("bvwbjplbgvbhsrlpgdmjqwftvncz"));
+    assert_eq!(Some(23), find_start_of_message("nppdvjthqldpwncqszvftbrmjlhg"));
+    assert_eq!(Some(29), find_start_of_message("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
+    assert_eq!(Some(26), find_start_of_message("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
+    match find_start_of_message(&input_string) {
+        Some(index) => println!("End of first start-of-message marker at index:\t {}", index),
+        None => println!("No start-of-message marker found"),
+    }
+
+    // the streaming decoder has to agree without reading the whole signal up front
+    let streamed = "äöü_mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes();
+    let events = decode_stream(streamed, &[START_OF_PACKET_WINDOW, START_OF_MESSAGE_WINDOW], |_event| ()).unwrap();
+    assert_eq!(vec![MarkerEvent { window: 4, char_offset: 4, byte_offset: 7 }, MarkerEvent { window: 14, char_offset: 23, byte_offset: 26 }], events);
+    assert!(decode_stream(&[b'a', 0xff, b'b'][..], &[2], |_event| ()).is_err());
+    assert_eq!(Vec::<MarkerEvent>::new(), decode_stream("aaaa\r\n".as_bytes(), &[2], |_event| ()).unwrap());
+    assert_eq!(vec![find_marker("aa b\n", 2)], decode_stream("aa b\n".as_bytes(), &[2], |_event| ()).unwrap().iter().map(|event| Some(event.char_offset)).collect::<Vec<_>>());
+    let input_file = File::open(Path::new("./src/input.txt")).unwrap();
+    let events = decode_stream(BufReader::new(input_file), &[START_OF_PACKET_WINDOW, START_OF_MESSAGE_WINDOW], |_event| ()).unwrap();
+    assert_eq!(vec![find_start_of_packet(&input_string), find_start_of_message(&input_string)], events.iter().map(|event| Some(event.char_offset)).collect::<Vec<_>>());
+
+    // every marker splits the signal, the first one has to match the single marker search
+    let segmentation = segment_signal("abcaaabc", 3).unwrap();
+    assert_eq!(vec![3, 4, 8], segmentation.markers);
+    assert_eq!(Span { start: 0, end: 3 }, segmentation.preamble);
+    assert_eq!(vec![Span { start: 3, end: 4 }, Span { start: 4, end: 8 }, Span { start: 8, end: 8 }], segmentation.frames);
+    assert_eq!(Span { start: 4, end: 7 }, segmentation.longest_gap);
+    assert_eq!(Span { start: 0, end: 6 }, segment_signal("abcabc", 4).unwrap().longest_gap);
+    assert!(segment_signal("abc", 0).is_none());
+    for window in [START_OF_PACKET_WINDOW, START_OF_MESSAGE_WINDOW] {
+        let segmentation = segment_signal(&input_string, window).unwrap();
+        assert_eq!(find_marker(&input_string, window), segmentation.markers.first().copied());
+        assert_eq!(segmentation.length - segmentation.preamble.end, segmentation.frames.iter().map(Span::len).sum::<usize>());
+    }
 
-    assert_eq!(19, find_start_of_message(&String::from("mjqjpqmgbljsphdztnvjfqwrcgsmlb")));
-    assert_eq!(23, find_start_of_message(&String::from("bvwbjplbgvbhsrlpgdmjqwftvncz")));
-    assert_eq!(23, find_start_of_message(&String::from("nppdvjthqldpwncqszvftbrmjlhg")));
-    assert_eq!(29, find_start_of_message(&String::from("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg")));
-    assert_eq!(26, find_start_of_message(&String::from("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw")));
-    println!("End of first start-of-message marker at index:\t {}", find_start_of_message(&input_string));
+    // round trip: the decoder has to find exactly the markers the encoder inserted and recover every payload
+    let encoded = encode_frames("zz", &["hello", "", "elves"], 4).unwrap();
+    assert_eq!("zzzzabcchheelllloobcddceffeellvveess", encoded.signal);
+    assert_eq!(vec![7, 21, 25], encoded.markers);
+    assert!(encode_frames("", &["a"], 2).is_err());
 
     /******************************************************
      * Reading input file...
@@ -32,35 +176,353 @@ fn main() {
      ******************************************************/
 }
 
-fn find_start_of_message(input_string: &String) -> usize {
-    for i in 0..input_string.len()-4 {
-        let start_marker = &input_string[i..i+4];
-        let start_marker_unique_chars = start_marker.chars().collect::<HashSet<char>>();
-        if start_marker_unique_chars.len() == 4 {
-            let message_marker = &input_string[i..i+14];
-            let message_marker_unique_chars = message_marker.chars().collect::<HashSet<char>>().len();
-            if message_marker_unique_chars == 14 {
-                return i+14;
+fn find_start_of_message(input_string: &str) -> Option<usize> {
+    find_marker(input_string, START_OF_MESSAGE_WINDOW)
+}
+
+fn find_start_of_packet(input_string: &str) -> Option<usize> {
+    find_marker(input_string, START_OF_PACKET_WINDOW)
+}
+
+// number of characters read once the first window of distinct characters is complete
+fn find_marker(input_string: &str, window: usize) -> Option<usize> {
+    let mut detector = MarkerDetector::new(window)?;
+    input_string.trim_end().chars().find_map(|c| detector.push(c)).map(|event| event.char_offset)
+}
+
+impl MarkerDetector {
+    fn new(window: usize) -> Option<MarkerDetector> {
+        if window == 0 {
+            return None;
+        }
+        Some(MarkerDetector { window, recent: VecDeque::with_capacity(window + 1), counts: HashMap::new(), duplicates: 0, chars_read: 0, bytes_read: 0 })
+    }
+
+    // emits an event whenever the latest window characters are all distinct
+    fn push(&mut self, c: char) -> Option<MarkerEvent> {
+        self.chars_read += 1;
+        self.bytes_read += c.len_utf8();
+        self.recent.push_back(c);
+        let count = self.counts.entry(c).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            self.duplicates += 1;
+        }
+        if self.recent.len() > self.window {
+            let dropped = self.recent.pop_front().unwrap();
+            let count = self.counts.get_mut(&dropped).unwrap();
+            *count -= 1;
+            if *count == 1 {
+                self.duplicates -= 1;
             }
         }
+        if self.recent.len() == self.window && self.duplicates == 0 {
+            return Some(MarkerEvent { window: self.window, char_offset: self.chars_read, byte_offset: self.bytes_read });
+        }
+        None
+    }
+}
+
+// reads a single utf-8 encoded character, None at the end of the stream
+fn read_char<R: BufRead>(reader: &mut R) -> io::Result<Option<char>> {
+    let mut bytes = [0u8; 4];
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    reader.read_exact(&mut bytes[..1])?;
+    let length = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        byte => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid utf-8 start byte {:#04x}", byte))),
+    };
+    reader.read_exact(&mut bytes[1..length])?;
+    match std::str::from_utf8(&bytes[..length]) {
+        Ok(decoded) => Ok(decoded.chars().next()),
+        Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+    }
+}
+
+// reads the signal character by character until every window found its first marker or the stream ends
+fn decode_stream<R: BufRead>(mut reader: R, windows: &[usize], mut on_event: impl FnMut(&MarkerEvent)) -> io::Result<Vec<MarkerEvent>> {
+    let mut detectors: Vec<MarkerDetector> = windows.iter().filter_map(|window| MarkerDetector::new(*window)).collect();
+    let mut events = Vec::new();
+    // whitespace only belongs to the signal once more characters follow, as the whole signal is trimmed at its end
+    let mut held_back = Vec::new();
+    while !detectors.is_empty() {
+        let c = match read_char(&mut reader)? {
+            Some(c) => c,
+            None => break,
+        };
+        if c.is_whitespace() {
+            held_back.push(c);
+            continue;
+        }
+        for c in held_back.drain(..).chain([c]) {
+            let mut index = 0;
+            while index < detectors.len() {
+                match detectors[index].push(c) {
+                    Some(event) => {
+                        on_event(&event);
+                        events.push(event);
+                        detectors.remove(index);
+                    }
+                    None => index += 1,
+                }
+            }
+        }
+    }
+    Ok(events)
+}
+
+// every window given by --window=<size>, or the start-of-packet and start-of-message windows
+fn parse_windows(args: &[String]) -> Vec<usize> {
+    let windows: Vec<usize> = args
+        .iter()
+        .filter_map(|arg| arg.strip_prefix("--window="))
+        .map(|window| window.parse::<usize>().unwrap_or_else(|error| panic!("Invalid window {:?} - error: {}", window, error)))
+        .collect();
+    if windows.is_empty() {
+        return vec![START_OF_PACKET_WINDOW, START_OF_MESSAGE_WINDOW];
+    }
+    windows
+}
+
+impl Span {
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+fn find_all_markers(input_string: &str, window: usize) -> Vec<usize> {
+    let mut detector = match MarkerDetector::new(window) {
+        Some(detector) => detector,
+        None => return Vec::new(),
+    };
+    input_string.trim_end().chars().filter_map(|c| detector.push(c)).map(|event| event.char_offset).collect()
+}
+
+fn segment_signal(input_string: &str, window: usize) -> Option<Segmentation> {
+    MarkerDetector::new(window)?;
+    let length = input_string.trim_end().chars().count();
+    let markers = find_all_markers(input_string, window);
+    let preamble = Span { start: 0, end: markers.first().copied().unwrap_or(length) };
+    let frames: Vec<Span> = markers
+        .iter()
+        .enumerate()
+        .map(|(index, start)| Span { start: *start, end: markers.get(index + 1).copied().unwrap_or(length) })
+        .collect();
+
+    // the character at index offset - 1 completes a marker, so gaps lie strictly between those
+    let mut longest_gap = Span { start: 0, end: 0 };
+    let mut gap_start = 0;
+    for end in markers.iter().map(|offset| offset - 1).chain([length]) {
+        if end - gap_start > longest_gap.len() {
+            longest_gap = Span { start: gap_start, end };
+        }
+        gap_start = end + 1;
+    }
+    Some(Segmentation { window, length, markers, preamble, frames, longest_gap })
+}
+
+fn print_segmentation(segmentation: &Segmentation) {
+    println!("Window of {} distinct characters:", segmentation.window);
+    println!("\t{} markers in {} characters, first one after {} characters", segmentation.markers.len(), segmentation.length, segmentation.preamble.end);
+    let lengths: Vec<usize> = segmentation.frames.iter().map(Span::len).collect();
+    if let (Some(shortest), Some(longest)) = (lengths.iter().min(), lengths.iter().max()) {
+        let average = lengths.iter().sum::<usize>() as f64 / lengths.len() as f64;
+        println!("\t{} frames of {} to {} characters, {:.2} on average", lengths.len(), shortest, longest, average);
+    }
+    let gap = segmentation.longest_gap;
+    println!("\tLongest run without a marker: {} characters at [{}, {})", gap.len(), gap.start, gap.end);
+}
+
+fn span_to_json(span: &Span) -> String {
+    format!("{{\"start\": {}, \"end\": {}, \"length\": {}}}", span.start, span.end, span.len())
+}
+
+fn segmentations_to_json(segmentations: &[Segmentation]) -> String {
+    let entries: Vec<String> = segmentations
+        .iter()
+        .map(|segmentation| {
+            let markers: Vec<String> = segmentation.markers.iter().map(|offset| offset.to_string()).collect();
+            let frames: Vec<String> = segmentation.frames.iter().map(|frame| format!("      {}", span_to_json(frame))).collect();
+            format!(
+                "  {{\n    \"window\": {},\n    \"length\": {},\n    \"markers\": [{}],\n    \"preamble\": {},\n    \"longest_gap\": {},\n    \"frames\": [\n{}\n    ]\n  }}",
+                segmentation.window,
+                segmentation.length,
+                markers.join(", "),
+                span_to_json(&segmentation.preamble),
+                span_to_json(&segmentation.longest_gap),
+                frames.join(",\n")
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", entries.join(",\n"))
+}
+
+impl Xorshift {
+    fn new(seed: u64) -> Xorshift {
+        Xorshift { state: seed.max(1) }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64) < probability * (1u64 << 53) as f64
+    }
+}
+
+fn random_text(rng: &mut Xorshift, max_length: u64) -> String {
+    (0..rng.below(max_length + 1)).map(|_index| (b'a' + rng.below(26) as u8) as char).collect()
+}
+
+// markers take up to 26 distinct letters, and windows below three would find markers within the doubled payloads
+fn check_frame_window(window: usize) -> Result<(), String> {
+    match (3..=26).contains(&window) {
+        true => Ok(()),
+        false => Err(format!("Window has to be between 3 and 26 characters, got {}", window)),
+    }
+}
+
+// Every character outside of a marker is sent twice, so any three consecutive characters contain a repeat.
+// A marker starts with the character sent right before it and its last character is repeated once as a guard,
+// which keeps the windows overlapping its edges from looking like a marker as well.
+fn encode_frames(preamble: &str, payloads: &[&str], window: usize) -> Result<EncodedSignal, String> {
+    check_frame_window(window)?;
+    let mut signal: String = preamble.chars().flat_map(|c| [c, c]).collect();
+    let mut markers = Vec::new();
+    for (index, payload) in payloads.iter().enumerate() {
+        // the first marker character is already part of the signal if it repeats the previous one
+        let previous = signal.chars().last();
+        let letters = ('a'..='z').cycle().skip(index % 26).take(26);
+        let marker: Vec<char> = letters.filter(|letter| Some(*letter) != previous).take(window - previous.map_or(0, |_previous| 1)).collect();
+        signal.extend(&marker);
+        markers.push(signal.chars().count());
+        signal.push(marker[marker.len() - 1]);
+        signal.extend(payload.chars().flat_map(|c| [c, c]));
+    }
+    Ok(EncodedSignal { signal, markers })
+}
+
+fn decode_payload(sent: &[char]) -> Result<String, String> {
+    let (guard, doubled) = match sent.split_first() {
+        Some(split) => split,
+        None => return Err("Frame is missing its guard character".to_string()),
+    };
+    if doubled.len() % 2 != 0 {
+        return Err(format!("Frame has an odd length of {} characters after the guard {:?}", doubled.len(), guard));
+    }
+    let mut payload = String::new();
+    for (index, pair) in doubled.chunks(2).enumerate() {
+        if pair[0] != pair[1] {
+            return Err(format!("Characters {:?} and {:?} of pair {} differ", pair[0], pair[1], index));
+        }
+        payload.push(pair[0]);
     }
-    panic!("No message found...");
+    Ok(payload)
 }
 
-fn find_start_of_packet(input_string: &String) -> usize {
-    for i in 0..input_string.len()-4 {
-        let start_marker = &input_string[i..i+4];
-        let unique_chars = start_marker.chars().collect::<HashSet<char>>();
-        if unique_chars.len() == 4 {
-            return i+4;
+fn decode_frames(signal: &str, window: usize) -> Vec<DecodedFrame> {
+    let chars: Vec<char> = signal.trim_end().chars().collect();
+    let markers = find_all_markers(signal, window);
+    markers
+        .iter()
+        .enumerate()
+        .map(|(index, marker)| {
+            // a frame ends where the next marker starts, the overlapping first character belongs to both
+            let end = markers.get(index + 1).map_or(chars.len(), |next| (next - window + 1).max(*marker));
+            DecodedFrame { marker: *marker, payload: decode_payload(&chars[*marker..end]) }
+        })
+        .collect()
+}
+
+// replaces each character by a random letter with the given probability, returns the number of changes
+fn inject_noise(signal: &str, rate: f64, rng: &mut Xorshift) -> (String, usize) {
+    let mut changed = 0;
+    let noisy = signal
+        .chars()
+        .map(|c| {
+            if !rng.chance(rate) {
+                return c;
+            }
+            let replacement = (b'a' + rng.below(26) as u8) as char;
+            if replacement != c {
+                changed += 1;
+            }
+            replacement
+        })
+        .collect();
+    (noisy, changed)
+}
+
+// without noise the decoder has to find exactly the markers the encoder inserted and recover every payload
+fn check_round_trips(rounds: usize, seed: u64) {
+    let mut rng = Xorshift::new(seed);
+    for _round in 0..rounds {
+        let window = 3 + rng.below(24) as usize;
+        let preamble = random_text(&mut rng, 10);
+        let payloads: Vec<String> = (0..rng.below(8)).map(|_index| random_text(&mut rng, 20)).collect();
+        let payloads: Vec<&str> = payloads.iter().map(String::as_str).collect();
+        let encoded = encode_frames(&preamble, &payloads, window).unwrap();
+        assert_eq!(encoded.markers, find_all_markers(&encoded.signal, window));
+        let decoded = decode_frames(&encoded.signal, window);
+        assert_eq!(encoded.markers, decoded.iter().map(|frame| frame.marker).collect::<Vec<_>>());
+        assert_eq!(payloads, decoded.iter().map(|frame| frame.payload.as_deref().unwrap()).collect::<Vec<_>>());
+        assert_eq!(encoded.signal, inject_noise(&encoded.signal, 0.0, &mut rng).0);
+    }
+    println!("Encoded and decoded {} random signals without noise", rounds);
+}
+
+fn simulate_channel(window: usize, frame_count: usize, noise: f64, seed: u64) {
+    let mut rng = Xorshift::new(seed);
+    let preamble = random_text(&mut rng, 10);
+    let payloads: Vec<String> = (0..frame_count).map(|_index| random_text(&mut rng, 20)).collect();
+    let payloads: Vec<&str> = payloads.iter().map(String::as_str).collect();
+    let encoded = encode_frames(&preamble, &payloads, window).unwrap_or_else(|error| panic!("Invalid input! {}", error));
+    println!("Sending {} frames in {} characters...", payloads.len(), encoded.signal.chars().count());
+    let (received, changed) = inject_noise(&encoded.signal, noise, &mut rng);
+    println!("Noise changed {} characters", changed);
+    if _DEBUG {
+        println!("sent:     {}\nreceived: {}", encoded.signal, received);
+    }
+
+    let decoded = decode_frames(&received, window);
+    let spurious = decoded.iter().filter(|frame| !encoded.markers.contains(&frame.marker)).count();
+    let mut intact = 0;
+    for (marker, payload) in encoded.markers.iter().zip(&payloads) {
+        match decoded.iter().find(|frame| frame.marker == *marker) {
+            None => println!("Frame at {:>4} lost its marker", marker),
+            Some(DecodedFrame { payload: Err(error), .. }) => println!("Frame at {:>4} corrupted: {}", marker, error),
+            Some(DecodedFrame { payload: Ok(received), .. }) if received != payload => println!("Frame at {:>4} altered: {:?} instead of {:?}", marker, received, payload),
+            Some(_) => intact += 1,
         }
     }
-    panic!("No packet found...");
+    println!("Recovered {} of {} frames intact, {} spurious markers", intact, payloads.len(), spurious);
+}
+
+fn print_marker_event(event: &MarkerEvent) {
+    let marker = match event.window {
+        START_OF_PACKET_WINDOW => "Start-of-packet marker".to_string(),
+        START_OF_MESSAGE_WINDOW => "Start-of-message marker".to_string(),
+        window => format!("Marker of {} distinct characters", window),
+    };
+    println!("{} complete after {} characters ({} bytes)", marker, event.char_offset, event.byte_offset);
 }
 
 fn read_input() -> String {
     let input_path = Path::new("./src/input.txt");
-    let mut input_file = match File::open(&input_path) {
+    let mut input_file = match File::open(input_path) {
         Err(error) => panic!("Failed to open {} - error: {}", input_path.display(), error),
         Ok(file) => file,
     };
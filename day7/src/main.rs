@@ -3,26 +3,169 @@
  * https://adventofcode.com/2022/day/7
  ********************************************/
 
-use std::{collections::HashMap, fs::File, io::Read, path::Path};
-
-use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env, fmt,
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
 
 const _DEBUG: bool = false;
+const TOTAL_DISK_SPACE: u64 = 70000000;
+const REQUIRED_SPACE: u64 = 30000000;
+// up to this many bytes to free, freeable sizes are kept as bitsets of 16 MiB at most, beyond as sorted lists
+const PLAN_MAX_BITSET_SIZE: u64 = 1 << 27;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NodeKind {
+    Directory,
+    File,
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    kind: NodeKind,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    size: u64, // own size of a file, cached aggregate size of a directory
+}
+
+// all nodes live in one vector and refer to each other by index, the root directory is at index 0
+#[derive(Debug)]
+struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+// line numbers start at 1, paths are absolute
+#[derive(Debug, PartialEq)]
+enum Inconsistency {
+    ConflictingFileSize { line: usize, path: String, size: u64, previous_size: u64 },
+    ConflictingKind { line: usize, path: String },
+    UnlistedDirectory { line: usize, path: String },
+    MissingFromRelisting { line: usize, path: String },
+    ParentOfRoot { line: usize },
+    OutputWithoutListing { line: usize, text: String },
+    UnknownCommand { line: usize, text: String },
+    MalformedOutput { line: usize, text: String },
+}
+
+// entries of the `$ ls` currently being read
+#[derive(Debug)]
+struct Listing {
+    directory: usize,
+    line: usize,
+    entries: HashSet<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+struct DeletionPlan {
+    directories: Vec<usize>,
+    deleted: u64,
+    unused_after: u64,
+}
+
+// sizes below the needed space that can be freed from some position of the preorder onwards
+#[derive(Debug, Clone)]
+enum FreeableSizes {
+    Bits(Vec<u64>),
+    Sorted(Vec<u64>),
+}
+
+// directories in preorder, so that every subtree is one contiguous range of positions
+#[derive(Debug)]
+struct DeletionCandidates {
+    directories: Vec<usize>,
+    subtree_end: Vec<usize>, // position right after the subdirectories of each candidate
+    blocked: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TraversalOrder {
+    Depth,
+    Reverse,
+    Breadth,
+}
+
+// subdirectory names and files with their sizes
+type DirectoryEntries = (Vec<String>, Vec<(String, u64)>);
 
 #[derive(Debug)]
-struct FileInfo {
-    absolute_path: String,
-    size: i32,
+struct Interpreter {
+    file_system: FileSystem,
+    current: usize,
+    listing: Option<Listing>,
+    listed_entries: HashMap<usize, HashSet<usize>>,
+    inconsistencies: Vec<Inconsistency>,
 }
 
 fn main() {
+    // usage: day7 tree [--depth=<levels>] [--sizes]
+    //        day7 du [--min-size=<size>]
+    //        day7 plan [--disk=<size>] [--target=<size>] [--protect=<path> ...]
+    //        day7 <generate|compare> <directory> [--order=depth|reverse|breadth] [--revisit]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let option = |name: &str| args.iter().find_map(|arg| arg.strip_prefix(name));
+    if let Some(command @ ("generate" | "compare")) = args.first().map(String::as_str) {
+        let root = args.get(1).filter(|arg| !arg.starts_with("--")).unwrap_or_else(|| panic!("Invalid input! {} needs a directory", command));
+        let order = TraversalOrder::from_name(option("--order=").unwrap_or("depth")).unwrap_or_else(|error| panic!("Invalid input! {}", error));
+        let transcript = generate_transcript(Path::new(root), order, args.iter().any(|arg| arg == "--revisit")).unwrap_or_else(|error| panic!("Failed to walk {} - error: {}", root, error));
+        if command == "generate" {
+            print!("{}", transcript);
+            return;
+        }
+        let (file_system, inconsistencies) = interpret_transcript(&transcript);
+        for inconsistency in &inconsistencies {
+            println!("{}", inconsistency);
+        }
+        let real_sizes = measure_directory_sizes(Path::new(root)).unwrap_or_else(|error| panic!("Failed to walk {} - error: {}", root, error));
+        let parsed_sizes = group_and_sum_filesizes_by_directory_prefix(&file_system);
+        for mismatch in compare_directory_sizes(&parsed_sizes, &real_sizes) {
+            println!("{}", mismatch);
+        }
+        let matches = real_sizes.iter().filter(|(path, real_size)| parsed_sizes.get(*path) == Some(*real_size)).count();
+        println!("{} of {} directory sizes match", matches, real_sizes.len());
+        if inconsistencies.is_empty() && matches == real_sizes.len() {
+            check_traversal_orders(Path::new(root), &real_sizes);
+        }
+        return;
+    }
+
     println!("Reading input file...");
     let input_string = read_input();
 
-    let file_paths = parse_absolute_paths_and_sizes_per_file(&input_string);
-    let filesize_by_prefix = group_and_sum_filesizes_by_directory_prefix(&file_paths);
+    let (file_system, inconsistencies) = interpret_transcript(&input_string);
+    for inconsistency in &inconsistencies {
+        println!("{}", inconsistency);
+    }
+    let filesize_by_prefix = group_and_sum_filesizes_by_directory_prefix(&file_system);
+    match args.first().map(String::as_str) {
+        Some("tree") => {
+            let depth = option("--depth=").map(|depth| depth.parse::<usize>().unwrap_or_else(|error| panic!("Invalid depth {:?} - error: {}", depth, error)));
+            print!("{}", render_tree(&file_system, depth, args.iter().any(|arg| arg == "--sizes")));
+            return;
+        }
+        Some("du") => {
+            let min_size = option("--min-size=").map(|size| parse_size(size).unwrap_or_else(|error| panic!("Invalid input! {}", error)));
+            print!("{}", render_du(&filesize_by_prefix, min_size.unwrap_or(0)));
+            return;
+        }
+        Some("plan") => {
+            let size = |name: &str, default: u64| option(name).map_or(default, |size| parse_size(size).unwrap_or_else(|error| panic!("Invalid input! {}", error)));
+            let protected: Vec<&str> = args.iter().filter_map(|arg| arg.strip_prefix("--protect=")).collect();
+            match plan_deletion(&file_system, size("--disk=", TOTAL_DISK_SPACE), size("--target=", REQUIRED_SPACE), &protected) {
+                Ok(plan) => print_deletion_plan(&file_system, &plan),
+                Err(error) => println!("No deletion plan: {}", error),
+            }
+            return;
+        }
+        Some(command) => panic!("Invalid input! Unknown command {:?}", command),
+        None => (),
+    }
+    assert_eq!(Some(&file_system.nodes[FileSystem::ROOT].size), filesize_by_prefix.get("/"));
 
-    let sum_of_directories_smaller_than_100k: i32 = filesize_by_prefix
+    let sum_of_directories_smaller_than_100k: u64 = filesize_by_prefix
         .iter()
         .filter(|(_k, v)| **v <= 100000)
         .map(|(_k, v)| v)
@@ -31,10 +174,8 @@ fn main() {
     println!("Sum over sizes of directories each smaller than 100000: {:?}", sum_of_directories_smaller_than_100k);
     assert_eq!(1348005, sum_of_directories_smaller_than_100k);
 
-    const TOTAL_DISK_SPACE: i32 = 70000000;
-    const REQUIRED_SPACE: i32 = 30000000;
-    let total_used_space: &i32 = filesize_by_prefix.values().max().unwrap();
-    let total_unused_space: i32 = TOTAL_DISK_SPACE - total_used_space;
+    let total_used_space: u64 = file_system.nodes[FileSystem::ROOT].size;
+    let total_unused_space: u64 = TOTAL_DISK_SPACE - total_used_space;
     let to_be_deleted_space = REQUIRED_SPACE - total_unused_space;
 
     let size_of_smallest_directory_to_be_deleted = filesize_by_prefix
@@ -46,6 +187,64 @@ fn main() {
     println!("Size of smallest directory that can be deleted to free up enough space: {:?}", size_of_smallest_directory_to_be_deleted);
     assert_eq!(12785886, *size_of_smallest_directory_to_be_deleted);
 
+    // files of the same size in one directory stay apart, listed but never visited directories are kept
+    let (sample, inconsistencies) = interpret_transcript("$ cd /\n$ ls\ndir empty\n10 a.txt\n10 b.txt\n$ cd sub\n$ ls\n5 c\n");
+    assert_eq!(vec![Inconsistency::UnlistedDirectory { line: 6, path: "/sub/".to_string() }], inconsistencies);
+    let sample_sizes = group_and_sum_filesizes_by_directory_prefix(&sample);
+    assert_eq!(Some(&25), sample_sizes.get("/"));
+    assert_eq!(Some(&0), sample_sizes.get("/empty/"));
+    assert_eq!(Some(&5), sample_sizes.get("/sub/"));
+    assert_eq!(Some(FileSystem::ROOT), sample.nodes[sample.find("/sub/c").unwrap()].parent.and_then(|sub| sample.nodes[sub].parent));
+    assert_eq!(None, sample.find("/missing"));
+
+    // multi-segment paths, repeated listings and everything the interpreter has to complain about
+    let transcript = [
+        "$ cd /", "$ ls", "dir a", "1 x", "$ cd a/b", "$ ls", "2 y y", "$ cd /a/b/../..", "$ ls", "1 x", "2 x", "dir a",
+        "$ cd ..", "$ cd /x", "$ pwd", "3", "$ cd a", "4 z", "$ ls", "dir b", "$ cd ./b", "$ ls", "dir q",
+    ];
+    let (sample, inconsistencies) = interpret_transcript(&transcript.join("\n"));
+    assert_eq!(Some(&3), group_and_sum_filesizes_by_directory_prefix(&sample).get("/"));
+    assert_eq!(Some(2), sample.find("/a/b/y y").map(|file| sample.nodes[file].size));
+    assert_eq!(
+        vec![
+            Inconsistency::UnlistedDirectory { line: 5, path: "/a/b/".to_string() },
+            Inconsistency::ConflictingFileSize { line: 11, path: "/x".to_string(), size: 2, previous_size: 1 },
+            Inconsistency::ParentOfRoot { line: 13 },
+            Inconsistency::ConflictingKind { line: 14, path: "/x".to_string() },
+            Inconsistency::UnknownCommand { line: 15, text: "$ pwd".to_string() },
+            Inconsistency::OutputWithoutListing { line: 16, text: "3".to_string() },
+            Inconsistency::OutputWithoutListing { line: 18, text: "4 z".to_string() },
+            Inconsistency::MissingFromRelisting { line: 22, path: "/a/b/y y".to_string() },
+        ],
+        inconsistencies
+    );
+    // several smaller directories may free enough space with less deleted than the single smallest one
+    let (sample, _inconsistencies) = interpret_transcript("$ cd /\n$ ls\ndir a\ndir b\ndir c\n$ cd a\n$ ls\n4 f\ndir d\n$ cd d\n$ ls\n3 g\n$ cd /b\n$ ls\n5 h\n$ cd /c\n$ ls\n1 i\n");
+    let plan_paths = |file_system: &FileSystem, plan: DeletionPlan| plan.directories.iter().map(|directory| file_system.path(*directory)).collect::<Vec<_>>();
+    assert_eq!(vec!["/a/d/", "/c/"], plan_paths(&sample, plan_deletion(&sample, 20, 11, &[]).unwrap()));
+    assert_eq!(vec!["/a/", "/c/"], plan_paths(&sample, plan_deletion(&sample, 20, 15, &["/b"]).unwrap()));
+    assert_eq!(Ok(DeletionPlan { directories: vec![], deleted: 0, unused_after: 7 }), plan_deletion(&sample, 20, 5, &[]));
+    assert!(plan_deletion(&sample, 20, 15, &["/b", "/a/d"]).is_err());
+    assert!(plan_deletion(&sample, 20, 5, &["/missing"]).is_err());
+    assert!(plan_deletion(&sample, 10, 5, &[]).is_err());
+    let (big, _inconsistencies) = interpret_transcript("$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\n16777217 big\n");
+    assert_eq!(vec!["/a/"], plan_paths(&big, plan_deletion(&big, 20_000_000, 20_000_000, &[]).unwrap()));
+    let (huge, _inconsistencies) = interpret_transcript("$ cd /\n$ ls\ndir a\ndir b\ndir c\n$ cd a\n$ ls\n300000000 f\n$ cd /b\n$ ls\n200000000 g\n$ cd /c\n$ ls\n150000001 h\n");
+    assert_eq!(vec!["/b/", "/c/"], plan_paths(&huge, plan_deletion(&huge, 1_000_000_000, 700_000_000, &[]).unwrap()));
+
+    assert!(generate_transcript(Path::new("./src/missing"), TraversalOrder::Depth, false).is_err());
+
+    let (sample, _inconsistencies) = interpret_transcript(&transcript.join("\n"));
+    let rendered = render_tree(&sample, None, true);
+    assert!(rendered.starts_with("- / (dir, size=3)\n  - a (dir, size=2)\n    - b (dir, size=2)\n      - q (dir, size=0)\n      - y y (file, size=2)\n"));
+    assert_eq!("- / (dir)\n  - a (dir)\n  - x (file, size=1)\n", render_tree(&sample, Some(1), false));
+    assert_eq!("      2B  /a/\n      2B  /a/b/\n", render_du(&group_and_sum_filesizes_by_directory_prefix(&sample), 1).lines().skip(1).map(|line| format!("{}\n", line)).collect::<String>());
+    assert_eq!(("584B", "28.4K", "48.4M"), (format_size(584).as_str(), format_size(29116).as_str(), format_size(50_786_032).as_str()));
+    assert_eq!(Ok(102_400), parse_size("100K"));
+    assert_eq!(Ok(1_610_612_736), parse_size("1.5G"));
+    assert!(parse_size("big").is_err());
+    assert_eq!(Inconsistency::MalformedOutput { line: 3, text: "x1 y".to_string() }, interpret_transcript("$ cd /\n$ ls\nx1 y").1[0]);
+
     /***********************************************************************************
      * Reading input file...
      * Successfully read ./src/input.txt
@@ -54,64 +253,640 @@ fn main() {
      ***********************************************************************************/
 }
 
-fn group_and_sum_filesizes_by_directory_prefix(file_infos: &Vec<FileInfo>) -> HashMap<String, i32> {
-    let mut filesize_by_prefix: HashMap<String, i32> = HashMap::new();
-    for file_info in file_infos {
-        let path_segments = file_info.absolute_path.split("/").collect::<Vec<&str>>();
-        for i in 1..path_segments.len() {
-            let prefix = format!("{}/", &path_segments[0..i].join("/"));
-            if filesize_by_prefix.contains_key(&prefix) {
-                let prefix_dir_size = filesize_by_prefix.get(&prefix).unwrap();
-                filesize_by_prefix.insert(prefix, prefix_dir_size + file_info.size);
-            } else {
-                filesize_by_prefix.insert(prefix, file_info.size);
+impl FileSystem {
+    const ROOT: usize = 0;
+
+    fn new() -> FileSystem {
+        let root = Node { name: String::new(), kind: NodeKind::Directory, parent: None, children: vec![], size: 0 };
+        FileSystem { nodes: vec![root] }
+    }
+
+    fn child(&self, parent: usize, name: &str) -> Option<usize> {
+        self.nodes[parent].children.iter().copied().find(|child| self.nodes[*child].name == name)
+    }
+
+    // returns the existing node if the parent already has a child of that name
+    fn add_node(&mut self, parent: usize, name: &str, kind: NodeKind, size: u64) -> usize {
+        if let Some(existing) = self.child(parent, name) {
+            return existing;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(Node { name: name.to_string(), kind, parent: Some(parent), children: vec![], size });
+        self.nodes[parent].children.push(index);
+        index
+    }
+
+    fn add_directory(&mut self, parent: usize, name: &str) -> usize {
+        self.add_node(parent, name, NodeKind::Directory, 0)
+    }
+
+    fn add_file(&mut self, parent: usize, name: &str, size: u64) -> usize {
+        self.add_node(parent, name, NodeKind::File, size)
+    }
+
+    // children are always added after their parent, so walking backwards sums up bottom to top
+    fn update_sizes(&mut self) {
+        for index in (0..self.nodes.len()).rev() {
+            if self.nodes[index].kind == NodeKind::Directory {
+                self.nodes[index].size = self.nodes[index].children.iter().map(|child| self.nodes[*child].size).sum();
             }
         }
     }
+
+    // absolute path, directories end with a slash
+    fn path(&self, index: usize) -> String {
+        let mut segments = vec![];
+        let mut current = Some(index);
+        while let Some(node) = current {
+            segments.push(self.nodes[node].name.as_str());
+            current = self.nodes[node].parent;
+        }
+        segments.reverse();
+        let path = if segments.len() == 1 { String::new() } else { segments.join("/") };
+        match self.nodes[index].kind {
+            NodeKind::Directory => format!("{}/", path),
+            NodeKind::File => path,
+        }
+    }
+
+    fn find(&self, absolute_path: &str) -> Option<usize> {
+        absolute_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(FileSystem::ROOT, |parent, segment| self.child(parent, segment))
+    }
+
+    fn directories(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|index| self.nodes[*index].kind == NodeKind::Directory)
+    }
+}
+
+fn group_and_sum_filesizes_by_directory_prefix(file_system: &FileSystem) -> HashMap<String, u64> {
+    let filesize_by_prefix: HashMap<String, u64> = file_system
+        .directories()
+        .map(|directory| (file_system.path(directory), file_system.nodes[directory].size))
+        .collect();
     if _DEBUG {
         println!("{:#?}", filesize_by_prefix);
     }
     filesize_by_prefix
 }
 
-fn parse_absolute_paths_and_sizes_per_file(input_string: &String) -> Vec<FileInfo> {
-    let mut file_infos: Vec<FileInfo> = vec![];
-    let mut path_segments = vec![];
-    let ls_output_pattern = Regex::new(r"^(?P<filesize>\d+) (?P<filename>[a-zA-Z0-9\.]+)$").unwrap();
-
-    for line in input_string.lines() {
-        if line.starts_with("$ cd") {
-            let cd_path = &line[5..];
-            if cd_path.eq("/") {
-                path_segments = vec!["".to_string()];
-            } else if cd_path.starts_with("..") {
-                // remove the last path segment to step into parent directory
-                path_segments.pop();
-            } else {
-                // append the new directory
-                path_segments.push(cd_path.to_string());
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Inconsistency::ConflictingFileSize { line, path, size, previous_size } => {
+                write!(f, "Line {}: {} listed with size {} after size {}", line, path, size, previous_size)
             }
-        } else if ls_output_pattern.is_match(line) {
-            // construct absolute path to file based on current path segments
-            let captures = ls_output_pattern.captures(line).unwrap();
-            let file_abs_path = format!("{}/{}", path_segments.join("/"), &captures["filesize"]);
-            let filesize: i32 = captures["filesize"].parse().unwrap();
-            let file_info = FileInfo {
-                absolute_path: file_abs_path,
-                size: filesize,
+            Inconsistency::ConflictingKind { line, path } => write!(f, "Line {}: {} is used both as file and as directory", line, path),
+            Inconsistency::UnlistedDirectory { line, path } => write!(f, "Line {}: cd into {} which was never listed", line, path),
+            Inconsistency::MissingFromRelisting { line, path } => write!(f, "Line {}: {} is missing from a repeated listing", line, path),
+            Inconsistency::ParentOfRoot { line } => write!(f, "Line {}: cd .. at the root directory", line),
+            Inconsistency::OutputWithoutListing { line, text } => write!(f, "Line {}: output {:?} without a preceding ls", line, text),
+            Inconsistency::UnknownCommand { line, text } => write!(f, "Line {}: unknown command {:?}", line, text),
+            Inconsistency::MalformedOutput { line, text } => write!(f, "Line {}: malformed ls output {:?}", line, text),
+        }
+    }
+}
+
+impl Interpreter {
+    fn new() -> Interpreter {
+        Interpreter { file_system: FileSystem::new(), current: FileSystem::ROOT, listing: None, listed_entries: HashMap::new(), inconsistencies: vec![] }
+    }
+
+    fn interpret_line(&mut self, line_number: usize, line: &str) {
+        if line.starts_with('$') {
+            self.finish_listing();
+        }
+        if let Some(cd_path) = line.strip_prefix("$ cd ") {
+            self.change_directory(line_number, cd_path);
+        } else if line == "$ ls" {
+            self.listing = Some(Listing { directory: self.current, line: line_number, entries: HashSet::new() });
+        } else if line.starts_with('$') {
+            self.inconsistencies.push(Inconsistency::UnknownCommand { line: line_number, text: line.to_string() });
+        } else if !line.is_empty() {
+            self.add_listed_entry(line_number, line);
+        }
+    }
+
+    // absolute or relative paths with any number of segments
+    fn change_directory(&mut self, line_number: usize, cd_path: &str) {
+        let mut target = if cd_path.starts_with('/') { FileSystem::ROOT } else { self.current };
+        for segment in cd_path.split('/').filter(|segment| !segment.is_empty() && *segment != ".") {
+            if segment == ".." {
+                match self.file_system.nodes[target].parent {
+                    Some(parent) => target = parent,
+                    None => self.inconsistencies.push(Inconsistency::ParentOfRoot { line: line_number }),
+                }
+                continue;
+            }
+            target = match self.file_system.child(target, segment) {
+                Some(child) if self.file_system.nodes[child].kind == NodeKind::Directory => child,
+                Some(child) => {
+                    let path = self.file_system.path(child);
+                    self.inconsistencies.push(Inconsistency::ConflictingKind { line: line_number, path });
+                    return;
+                }
+                None => {
+                    let child = self.file_system.add_directory(target, segment);
+                    let path = self.file_system.path(child);
+                    self.inconsistencies.push(Inconsistency::UnlistedDirectory { line: line_number, path });
+                    child
+                }
             };
-            file_infos.push(file_info);
+        }
+        self.current = target;
+    }
+
+    fn add_listed_entry(&mut self, line_number: usize, line: &str) {
+        let directory = match &self.listing {
+            Some(listing) => listing.directory,
+            None => {
+                self.inconsistencies.push(Inconsistency::OutputWithoutListing { line: line_number, text: line.to_string() });
+                return;
+            }
+        };
+        let (kind, name, size) = match line.split_once(' ') {
+            Some(("dir", name)) => (NodeKind::Directory, name, 0),
+            Some((size, name)) if size.parse::<u64>().is_ok() => (NodeKind::File, name, size.parse().unwrap()),
+            _ => {
+                self.inconsistencies.push(Inconsistency::MalformedOutput { line: line_number, text: line.to_string() });
+                return;
+            }
+        };
+        let entry = match self.file_system.child(directory, name) {
+            Some(existing) => {
+                let node = &self.file_system.nodes[existing];
+                if node.kind != kind {
+                    let path = self.file_system.path(existing);
+                    self.inconsistencies.push(Inconsistency::ConflictingKind { line: line_number, path });
+                } else if kind == NodeKind::File && node.size != size {
+                    // the first listing wins, a file is never counted twice
+                    let (path, previous_size) = (self.file_system.path(existing), node.size);
+                    self.inconsistencies.push(Inconsistency::ConflictingFileSize { line: line_number, path, size, previous_size });
+                }
+                existing
+            }
+            None => match kind {
+                NodeKind::Directory => self.file_system.add_directory(directory, name),
+                NodeKind::File => self.file_system.add_file(directory, name, size),
+            },
+        };
+        self.listing.as_mut().unwrap().entries.insert(entry);
+    }
+
+    // compares a repeated listing of a directory with everything listed for it before
+    fn finish_listing(&mut self) {
+        let listing = match self.listing.take() {
+            Some(listing) => listing,
+            None => return,
+        };
+        let listed = self.listed_entries.entry(listing.directory).or_default();
+        let first_listing = listed.is_empty();
+        let mut missing: Vec<usize> = listed.difference(&listing.entries).copied().collect();
+        listed.extend(listing.entries);
+        if first_listing {
+            return;
+        }
+        missing.sort_unstable();
+        for entry in missing {
+            let path = self.file_system.path(entry);
+            self.inconsistencies.push(Inconsistency::MissingFromRelisting { line: listing.line, path });
         }
     }
+}
+
+fn interpret_transcript(input_string: &str) -> (FileSystem, Vec<Inconsistency>) {
+    let mut interpreter = Interpreter::new();
+    for (index, line) in input_string.lines().enumerate() {
+        interpreter.interpret_line(index + 1, line.trim_end());
+    }
+    interpreter.finish_listing();
+    interpreter.file_system.update_sizes();
     if _DEBUG {
-        println!("{:#?}", file_infos);
+        println!("{:#?}", interpreter.file_system);
+    }
+    (interpreter.file_system, interpreter.inconsistencies)
+}
+
+// the puzzle's own format, children sorted by name, directories below depth levels are not expanded
+fn render_tree(file_system: &FileSystem, depth: Option<usize>, show_sizes: bool) -> String {
+    let mut rendered = String::new();
+    let mut stack = vec![(FileSystem::ROOT, 0)];
+    while let Some((index, level)) = stack.pop() {
+        let node = &file_system.nodes[index];
+        let name = if index == FileSystem::ROOT { "/" } else { node.name.as_str() };
+        let details = match (node.kind, show_sizes) {
+            (NodeKind::Directory, false) => "dir".to_string(),
+            (NodeKind::Directory, true) => format!("dir, size={}", node.size),
+            (NodeKind::File, _) => format!("file, size={}", node.size),
+        };
+        rendered.push_str(&format!("{}- {} ({})\n", "  ".repeat(level), name, details));
+        if depth.is_none_or(|depth| level < depth) {
+            let mut children = node.children.clone();
+            children.sort_by(|a, b| file_system.nodes[*b].name.cmp(&file_system.nodes[*a].name));
+            stack.extend(children.into_iter().map(|child| (child, level + 1)));
+        }
+    }
+    rendered
+}
+
+const SIZE_UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+
+// human readable size in powers of 1024 like `du -h`
+fn format_size(size: u64) -> String {
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < SIZE_UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{}B", size),
+        _ => format!("{:.1}{}", value, SIZE_UNITS[unit]),
+    }
+}
+
+// plain bytes or a number followed by one of the units of format_size
+fn parse_size(size: &str) -> Result<u64, String> {
+    let unit = SIZE_UNITS.iter().position(|unit| size.ends_with(unit));
+    let number = &size[..size.len() - unit.map_or(0, |_unit| 1)];
+    match number.parse::<f64>() {
+        Ok(value) if value >= 0.0 => Ok((value * 1024f64.powi(unit.unwrap_or(0) as i32)) as u64),
+        _ => Err(format!("Size {:?} is not a number with an optional unit of {}", size, SIZE_UNITS.join(", "))),
+    }
+}
+
+// directories sorted by size, largest first, ties by path
+fn render_du(filesize_by_prefix: &HashMap<String, u64>, min_size: u64) -> String {
+    let mut directories: Vec<(&String, &u64)> = filesize_by_prefix.iter().filter(|(_path, size)| **size >= min_size).collect();
+    directories.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    directories.iter().map(|(path, size)| format!("{:>8}  {}\n", format_size(**size), path)).collect()
+}
+
+// Picks directories none of which contains another, so that at least the target is unused afterwards while
+// as little as possible is deleted. Protected paths, their contents and the directories containing them stay.
+//
+// Walking the preorder backwards, the sizes freeable from a position onwards are those of the next position,
+// plus the candidate's size added to everything freeable after its subtree. Sizes below the needed space are
+// kept exactly, only the smallest size reaching it is remembered.
+fn plan_deletion(file_system: &FileSystem, disk_space: u64, target: u64, protected_paths: &[&str]) -> Result<DeletionPlan, String> {
+    let protected_nodes = protected_paths
+        .iter()
+        .map(|path| file_system.find(path).ok_or(format!("Protected path {} does not exist", path)))
+        .collect::<Result<Vec<usize>, String>>()?;
+    let used = file_system.nodes[FileSystem::ROOT].size;
+    if used > disk_space {
+        return Err(format!("{} used exceeds the disk size of {}", used, disk_space));
+    }
+    let unused = disk_space - used;
+    if unused >= target {
+        return Ok(DeletionPlan { directories: vec![], deleted: 0, unused_after: unused });
+    }
+    let needed = target - unused;
+
+    let candidates = DeletionCandidates::new(file_system, &protected_nodes);
+    let count = candidates.directories.len();
+
+    let sizes: Vec<u64> = candidates.directories.iter().map(|directory| file_system.nodes[*directory].size).collect();
+
+    // the position at which each freeable size first became reachable, enough to trace back a plan
+    let mut first_reached: Vec<(u64, usize)> = vec![(0, count)];
+    let mut reachable = FreeableSizes::new(needed);
+    // a subtree end is needed until the candidate owning the subtree is reached, at most one per level
+    let mut last_use: HashMap<usize, usize> = HashMap::new();
+    for position in 0..count {
+        last_use.entry(candidates.subtree_end[position]).or_insert(position);
+    }
+    let mut kept: HashMap<usize, FreeableSizes> = HashMap::new();
+    if last_use.contains_key(&count) {
+        kept.insert(count, reachable.clone());
+    }
+
+    let mut best: Option<(u64, usize, u64)> = None; // freed size, position, freed after its subtree
+    for position in (0..count).rev() {
+        let end = candidates.subtree_end[position];
+        if !candidates.blocked[position] {
+            let after_subtree = &kept[&end];
+            let size = sizes[position];
+            let reaching = match size >= needed {
+                true => Some(0),
+                false => after_subtree.first_from(needed - size),
+            };
+            if let Some(rest) = reaching {
+                if best.is_none_or(|(freed, _position, _rest)| size + rest < freed) {
+                    best = Some((size + rest, position, rest));
+                }
+            }
+            let fresh = reachable.add_shifted(after_subtree, size, needed);
+            first_reached.extend(fresh.into_iter().map(|freed| (freed, position)));
+        }
+        if last_use.get(&end) == Some(&position) {
+            kept.remove(&end);
+        }
+        if last_use.contains_key(&position) {
+            kept.insert(position, reachable.clone());
+        }
+    }
+
+    let (_freed, position, mut rest) = best.ok_or(format!("Deleting every unprotected directory frees less than the needed {}", needed))?;
+    let mut directories = vec![candidates.directories[position]];
+    while rest > 0 {
+        // every size is listed once, and a plan only takes a few lookups
+        let (_freed, position) = *first_reached.iter().find(|(freed, _position)| *freed == rest).unwrap();
+        directories.push(candidates.directories[position]);
+        rest -= sizes[position];
+    }
+    directories.sort_by_key(|directory| file_system.path(*directory));
+    let deleted = directories.iter().map(|directory| file_system.nodes[*directory].size).sum();
+    Ok(DeletionPlan { directories, deleted, unused_after: unused + deleted })
+}
+
+impl DeletionCandidates {
+    fn new(file_system: &FileSystem, protected_nodes: &[usize]) -> DeletionCandidates {
+        let mut directories = vec![];
+        let mut depths = vec![];
+        let mut stack = vec![(FileSystem::ROOT, 0)];
+        while let Some((directory, depth)) = stack.pop() {
+            directories.push(directory);
+            depths.push(depth);
+            let children = file_system.nodes[directory].children.iter().rev();
+            stack.extend(children.filter(|child| file_system.nodes[**child].kind == NodeKind::Directory).map(|child| (*child, depth + 1)));
+        }
+        // a subtree ends at the first following directory that is not a descendant
+        let subtree_end = (0..directories.len())
+            .map(|position| (position + 1..directories.len()).find(|next| depths[*next] <= depths[position]).unwrap_or(directories.len()))
+            .collect();
+        let subtree_end: Vec<usize> = subtree_end;
+
+        let mut blocked = vec![false; directories.len()];
+        for protected in protected_nodes {
+            let mut current = Some(*protected);
+            while let Some(node) = current {
+                if let Some(position) = directories.iter().position(|directory| *directory == node) {
+                    blocked[position] = true;
+                }
+                current = file_system.nodes[node].parent;
+            }
+            if let Some(position) = directories.iter().position(|directory| directory == protected) {
+                blocked[position..subtree_end[position]].fill(true);
+            }
+        }
+        DeletionCandidates { directories, subtree_end, blocked }
+    }
+}
+
+impl FreeableSizes {
+    // nothing freed yet
+    fn new(needed: u64) -> FreeableSizes {
+        match needed <= PLAN_MAX_BITSET_SIZE {
+            true => {
+                let mut bits = vec![0u64; needed.div_ceil(64) as usize];
+                bits[0] = 1;
+                FreeableSizes::Bits(bits)
+            }
+            false => FreeableSizes::Sorted(vec![0]),
+        }
+    }
+
+    // smallest freeable size of at least the given one
+    fn first_from(&self, from: u64) -> Option<u64> {
+        match self {
+            FreeableSizes::Bits(bits) => first_bit_from(bits, from as usize).map(|size| size as u64),
+            FreeableSizes::Sorted(sizes) => sizes.get(sizes.partition_point(|size| *size < from)).copied(),
+        }
+    }
+
+    // adds every size of the source grown by the given one that stays below the needed space,
+    // returning the sizes that were not freeable before
+    fn add_shifted(&mut self, source: &FreeableSizes, shift: u64, needed: u64) -> Vec<u64> {
+        let mut fresh_sizes = vec![];
+        match (self, source) {
+            (FreeableSizes::Bits(bits), FreeableSizes::Bits(source)) => {
+                let mut added = bits.clone();
+                shift_or(&mut added, source, shift as usize);
+                mask_bits(&mut added, needed as usize);
+                for (word, (new, old)) in added.iter().zip(bits.iter()).enumerate() {
+                    let mut fresh = new & !old;
+                    while fresh != 0 {
+                        fresh_sizes.push((word * 64 + fresh.trailing_zeros() as usize) as u64);
+                        fresh &= fresh - 1;
+                    }
+                }
+                *bits = added;
+            }
+            (FreeableSizes::Sorted(sizes), FreeableSizes::Sorted(source)) => {
+                let mut merged = Vec::with_capacity(sizes.len() + source.len());
+                let mut old = sizes.iter().peekable();
+                for size in source.iter().map(|size| size + shift).take_while(|size| *size < needed) {
+                    while let Some(smaller) = old.next_if(|old| **old < size) {
+                        merged.push(*smaller);
+                    }
+                    if old.next_if_eq(&&size).is_none() {
+                        fresh_sizes.push(size);
+                    }
+                    merged.push(size);
+                }
+                merged.extend(old);
+                *sizes = merged;
+            }
+            _ => unreachable!("Freeable sizes of one plan are all kept the same way"),
+        }
+        fresh_sizes
+    }
+}
+
+// target |= source << shift
+fn shift_or(target: &mut [u64], source: &[u64], shift: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+    for index in (words..target.len()).rev() {
+        let mut shifted = source[index - words] << bits;
+        if bits > 0 && index > words {
+            shifted |= source[index - words - 1] >> (64 - bits);
+        }
+        target[index] |= shifted;
     }
-    file_infos
+}
+
+fn mask_bits(bits: &mut [u64], length: usize) {
+    if !length.is_multiple_of(64) {
+        let last = bits.len() - 1;
+        bits[last] &= (1u64 << (length % 64)) - 1;
+    }
+}
+
+fn first_bit_from(bits: &[u64], from: usize) -> Option<usize> {
+    let mut word = from / 64;
+    let mut masked = bits.get(word)? & (u64::MAX << (from % 64));
+    loop {
+        if masked != 0 {
+            return Some(word * 64 + masked.trailing_zeros() as usize);
+        }
+        word += 1;
+        masked = *bits.get(word)?;
+    }
+}
+
+fn print_deletion_plan(file_system: &FileSystem, plan: &DeletionPlan) {
+    println!("Delete {} directories:", plan.directories.len());
+    for directory in &plan.directories {
+        println!("{:>12}  {}", file_system.nodes[*directory].size, file_system.path(*directory));
+    }
+    println!("Deleted {} in total, {} unused afterwards", plan.deleted, plan.unused_after);
+}
+
+impl TraversalOrder {
+    fn from_name(name: &str) -> Result<TraversalOrder, String> {
+        match name {
+            "depth" => Ok(TraversalOrder::Depth),
+            "reverse" => Ok(TraversalOrder::Reverse),
+            "breadth" => Ok(TraversalOrder::Breadth),
+            _ => Err(format!("Unknown traversal order {:?}, expected depth, reverse or breadth", name)),
+        }
+    }
+}
+
+// names the transcript format can carry, everything else is left out by the generator and the measurement
+fn is_transcript_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['\n', '\r']) && name.trim_end() == name
+}
+
+// subdirectories and files with their sizes sorted by name, symbolic links are not followed
+fn read_entries(directory: &Path) -> io::Result<DirectoryEntries> {
+    let mut directories = vec![];
+    let mut files = vec![];
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) if is_transcript_name(&name) => name,
+            _ => continue,
+        };
+        let metadata = fs::symlink_metadata(entry.path())?;
+        if metadata.is_dir() {
+            directories.push(name);
+        } else if metadata.is_file() {
+            files.push((name, metadata.len()));
+        }
+    }
+    directories.sort();
+    files.sort();
+    Ok((directories, files))
+}
+
+fn push_listing(transcript: &mut String, directories: &[String], files: &[(String, u64)]) {
+    transcript.push_str("$ ls\n");
+    for directory in directories {
+        transcript.push_str(&format!("dir {}\n", directory));
+    }
+    for (name, size) in files {
+        transcript.push_str(&format!("{} {}\n", size, name));
+    }
+}
+
+// Depth first walks cd into each subdirectory and back out with cd .., breadth first jumps between directories
+// with absolute paths. Revisiting lists every directory a second time after its subdirectories were walked.
+fn generate_transcript(root: &Path, order: TraversalOrder, revisit: bool) -> io::Result<String> {
+    let mut transcript = String::from("$ cd /\n");
+    match order {
+        TraversalOrder::Depth | TraversalOrder::Reverse => generate_depth_first(root, order, revisit, &mut transcript)?,
+        TraversalOrder::Breadth => {
+            let mut queue = VecDeque::from([(root.to_path_buf(), String::from("/"))]);
+            let mut visited = vec![];
+            while let Some((directory, absolute_path)) = queue.pop_front() {
+                let (directories, files) = read_entries(&directory)?;
+                if absolute_path != "/" {
+                    transcript.push_str(&format!("$ cd {}\n", absolute_path));
+                }
+                push_listing(&mut transcript, &directories, &files);
+                for name in &directories {
+                    queue.push_back((directory.join(name), format!("{}{}/", absolute_path, name)));
+                }
+                visited.push((absolute_path, directories, files));
+            }
+            if revisit {
+                for (absolute_path, directories, files) in visited {
+                    transcript.push_str(&format!("$ cd {}\n", absolute_path));
+                    push_listing(&mut transcript, &directories, &files);
+                }
+            }
+        }
+    }
+    Ok(transcript)
+}
+
+fn generate_depth_first(directory: &Path, order: TraversalOrder, revisit: bool, transcript: &mut String) -> io::Result<()> {
+    let (mut directories, mut files) = read_entries(directory)?;
+    if order == TraversalOrder::Reverse {
+        directories.reverse();
+        files.reverse();
+    }
+    push_listing(transcript, &directories, &files);
+    for name in &directories {
+        transcript.push_str(&format!("$ cd {}\n", name));
+        generate_depth_first(&directory.join(name), order, revisit, transcript)?;
+        transcript.push_str("$ cd ..\n");
+    }
+    if revisit && !directories.is_empty() {
+        push_listing(transcript, &directories, &files);
+    }
+    Ok(())
+}
+
+// sums file sizes straight from the disk, keyed like group_and_sum_filesizes_by_directory_prefix
+fn measure_directory_sizes(root: &Path) -> io::Result<HashMap<String, u64>> {
+    let mut sizes = HashMap::new();
+    measure_directory(root, String::from("/"), &mut sizes)?;
+    Ok(sizes)
+}
+
+fn measure_directory(directory: &Path, absolute_path: String, sizes: &mut HashMap<String, u64>) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) if is_transcript_name(&name) => name,
+            _ => continue,
+        };
+        let metadata = fs::symlink_metadata(entry.path())?;
+        if metadata.is_dir() {
+            size += measure_directory(&entry.path(), format!("{}{}/", absolute_path, name), sizes)?;
+        } else if metadata.is_file() {
+            size += metadata.len();
+        }
+    }
+    sizes.insert(absolute_path, size);
+    Ok(size)
+}
+
+// every way of walking a directory has to produce a transcript that reproduces the sizes measured on the disk
+fn check_traversal_orders(root: &Path, real_sizes: &HashMap<String, u64>) {
+    for order in [TraversalOrder::Depth, TraversalOrder::Reverse, TraversalOrder::Breadth] {
+        for revisit in [false, true] {
+            let (generated, inconsistencies) = interpret_transcript(&generate_transcript(root, order, revisit).unwrap());
+            assert_eq!(Vec::<Inconsistency>::new(), inconsistencies);
+            assert_eq!(Vec::<String>::new(), compare_directory_sizes(&group_and_sum_filesizes_by_directory_prefix(&generated), real_sizes));
+        }
+    }
+    println!("Every traversal order reproduces the same sizes");
+}
+
+fn compare_directory_sizes(parsed: &HashMap<String, u64>, real: &HashMap<String, u64>) -> Vec<String> {
+    let mut paths: Vec<&String> = parsed.keys().chain(real.keys()).collect::<HashSet<_>>().into_iter().collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| match (parsed.get(path), real.get(path)) {
+            (Some(parsed_size), Some(real_size)) if parsed_size == real_size => None,
+            (Some(parsed_size), Some(real_size)) => Some(format!("{} parsed with {} but is {} on disk", path, parsed_size, real_size)),
+            (Some(_parsed_size), None) => Some(format!("{} parsed but missing on disk", path)),
+            (None, _real_size) => Some(format!("{} on disk but missing from the transcript", path)),
+        })
+        .collect()
 }
 
 fn read_input() -> String {
     let input_path = Path::new("./src/input.txt");
-    let mut input_file = match File::open(&input_path) {
+    let mut input_file = match File::open(input_path) {
         Err(error) => panic!("Failed to open {} - error: {}", input_path.display(), error),
         Ok(file) => file,
     };